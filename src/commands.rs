@@ -0,0 +1,610 @@
+/*!
+ * Command execution for rotation and press actions
+ *
+ * Dispatches rotations and presses to the mode executors in `modes` and
+ * holds the process plumbing they share. Commands run on tokio's process
+ * API so a slow tool never blocks the OpenAction event loop.
+ */
+
+use crate::error::ActionError;
+use crate::logger;
+use crate::modes::{self, ActionExecutor};
+use crate::state::STATE;
+use crate::{ActionMode, DialPressAction, RotateSettings};
+use openaction::Instance;
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+
+/// N1_DRY_RUN set (to anything but empty or 0) when the plugin started
+static DRY_RUN_ENABLED: LazyLock<bool> = LazyLock::new(|| std::env::var("N1_DRY_RUN").is_ok_and(|v| !v.is_empty() && v != "0"));
+
+tokio::task_local! {
+    /// Whether the rotation or press running on this task is a dry run
+    static DRY_RUN: bool;
+}
+
+/// Whether the plugin runs in dry-run mode, logging commands instead of
+/// running them
+pub(crate) fn dry_run_enabled() -> bool {
+    *DRY_RUN_ENABLED
+}
+
+/// Whether the current rotation or press is a dry run. In-process backends
+/// check this after logging what they would do.
+pub(crate) fn dry_run() -> bool {
+    DRY_RUN.try_with(|dry_run| *dry_run).unwrap_or(false)
+}
+
+/// Where an action came from, exposed to custom commands as N1_* variables
+pub(crate) struct CommandContext {
+    pub(crate) device_id: String,
+    pub(crate) pressed: bool,
+}
+
+impl CommandContext {
+    pub(crate) fn new(instance: &Instance, pressed: bool) -> Self {
+        Self { device_id: instance.device_id.clone(), pressed }
+    }
+}
+
+/// What the dial shows after a rotation went through
+pub(crate) enum Feedback {
+    /// Just the tick
+    Done,
+    /// The new level in percent, for the level overlay
+    Level(u32),
+    /// A title, e.g. the output device switched to
+    Title(String),
+}
+
+/// Run a rotation in the background and report it on the instance, so the
+/// event loop is free for the next tick while the command runs
+pub(crate) fn spawn_rotation(instance_id: String, context: CommandContext, direction: i8, magnitude: u32, settings: RotateSettings) {
+    tokio::spawn(async move {
+        let result = execute_rotation(direction, magnitude, &context, &settings, dry_run_enabled()).await;
+        if let Some(instance) = openaction::get_instance(instance_id).await {
+            report_rotation(&instance, result, &settings).await;
+        }
+    });
+}
+
+/// Run a press action in the background, like `spawn_rotation`
+pub(crate) fn spawn_press_action(instance_id: String, action: DialPressAction, command: String, settings: RotateSettings) {
+    tokio::spawn(async move {
+        if let Some(instance) = openaction::get_instance(instance_id).await {
+            run_press_action(&instance, &action, &command, &settings).await;
+        }
+    });
+}
+
+/// Show the outcome of a rotation on the instance: the new level if the
+/// mode reported one and the overlay is on, a title the mode reported,
+/// otherwise a tick
+pub(crate) async fn report_rotation(instance: &Instance, result: Result<Feedback, ActionError>, settings: &RotateSettings) {
+    match result {
+        Ok(Feedback::Level(level)) if settings.show_level => show_level_overlay(instance, &settings.mode(), level).await,
+        Ok(Feedback::Title(title)) => {
+            let _ = instance.set_title(Some(title), None).await;
+            let _ = instance.show_ok().await;
+        }
+        Ok(_) => {
+            let _ = instance.show_ok().await;
+        }
+        Err(e) => {
+            if e.log("Rotation action failed") {
+                let _ = instance.show_alert().await;
+            }
+        }
+    }
+}
+
+/// Record a mute state read from the backend and, when it changed, show it
+/// on every dial that toggles it: state 1 is muted, 0 unmuted
+pub(crate) async fn sync_mute(mode: &ActionMode, muted: bool) {
+    if !STATE.set_muted(mode, muted) {
+        return;
+    }
+    for instance_id in STATE.mute_watchers(mode) {
+        if let Some(instance) = openaction::get_instance(instance_id).await {
+            let _ = instance.set_state(u16::from(muted)).await;
+        }
+    }
+}
+
+/// How long the level overlay stays up after the last rotation
+const LEVEL_OVERLAY: Duration = Duration::from_millis(1000);
+
+/// Replace the dial's image with a bar showing `level` percent, and put the
+/// normal image back once the dial has been still for `LEVEL_OVERLAY`
+async fn show_level_overlay(instance: &Instance, mode: &ActionMode, level: u32) {
+    if let Err(e) = instance.set_image(Some(level_image(mode.title(), level)), None).await {
+        log::warn!("Failed to show level on {}: {}", instance.instance_id, e);
+        return;
+    }
+    let instance_id = instance.instance_id.clone();
+    let timer = tokio::spawn({
+        let instance_id = instance_id.clone();
+        async move {
+            tokio::time::sleep(LEVEL_OVERLAY).await;
+            if !STATE.fire_overlay_clear(&instance_id) {
+                return;
+            }
+            if let Some(instance) = openaction::get_instance(instance_id).await {
+                let _ = instance.set_image(None::<String>, None).await;
+            }
+        }
+    });
+    STATE.start_overlay_clear(&instance_id, timer);
+}
+
+/// SVG data URL with the mode name, the level and a horizontal bar
+fn level_image(label: &str, level: u32) -> String {
+    let level = level.min(100);
+    format!(
+        "data:image/svg+xml;charset=utf8,<svg xmlns='http://www.w3.org/2000/svg' width='144' height='144' viewBox='0 0 144 144'>\
+         <rect width='144' height='144' fill='rgb(20,20,20)'/>\
+         <text x='72' y='40' font-family='sans-serif' font-size='22' fill='rgb(200,200,200)' text-anchor='middle'>{}</text>\
+         <text x='72' y='84' font-family='sans-serif' font-size='34' font-weight='bold' fill='white' text-anchor='middle'>{}%</text>\
+         <rect x='12' y='104' width='120' height='16' rx='8' fill='rgb(60,60,60)'/>\
+         <rect x='12' y='104' width='{}' height='16' rx='8' fill='rgb(80,170,255)'/>\
+         </svg>",
+        label,
+        level,
+        level * 120 / 100
+    )
+}
+
+/// Run the configured mode's executor for a rotation of `magnitude` steps
+/// in `direction`. Returns what the mode reports after the rotation: a
+/// title, a level, or nothing. With `dry_run` the commands are logged
+/// instead of run and nothing is read back.
+pub(crate) async fn execute_rotation(
+    direction: i8,
+    magnitude: u32,
+    context: &CommandContext,
+    settings: &RotateSettings,
+    dry_run: bool,
+) -> Result<Feedback, ActionError> {
+    rotate_with(modes::executor(&settings.mode()), direction, magnitude, context, settings, dry_run).await
+}
+
+/// Run a rotation on `executor`, which `execute_rotation` looks up by mode
+async fn rotate_with(
+    executor: &dyn ActionExecutor,
+    direction: i8,
+    magnitude: u32,
+    context: &CommandContext,
+    settings: &RotateSettings,
+    dry_run: bool,
+) -> Result<Feedback, ActionError> {
+    if let Some(reason) = executor.unavailable(settings) {
+        return Err(ActionError::Unavailable(reason));
+    }
+    let delta = i32::from(direction).saturating_mul(i32::try_from(magnitude).unwrap_or(i32::MAX));
+    if dry_run {
+        log::info!("Dry run: {:?} rotation of {} ({} x {})", settings.mode(), delta, direction, magnitude);
+        DRY_RUN.scope(true, executor.rotate(delta, context, settings)).await?;
+        return Ok(Feedback::Done);
+    }
+    // Past the first rotation of a burst the mode logs at debug
+    let quiet = !STATE.log_allowed("Rotation", &settings.mode());
+    logger::quiet(quiet, async {
+        executor.rotate(delta, context, settings).await?;
+        if let Some(title) = executor.title(settings).await {
+            return Ok(Feedback::Title(title));
+        }
+        Ok(executor.level(settings).await.map_or(Feedback::Done, Feedback::Level))
+    })
+    .await
+}
+
+/// Run a dial press action and report the result on the instance
+pub(crate) async fn run_press_action(instance: &Instance, action: &DialPressAction, command: &str, settings: &RotateSettings) {
+    let result = match action {
+        DialPressAction::None => return,
+        DialPressAction::CycleMode => {
+            let mut updated = settings.clone();
+            updated.mode = Some(settings.mode().next());
+            log::info!("Switching mode: {:?} -> {:?}", settings.mode(), updated.mode());
+            let result = instance.set_settings(&updated).await.map_err(Into::into);
+            if result.is_ok() {
+                STATE.track(&instance.instance_id, &instance.device_id, updated.mode.clone());
+            }
+            if result.is_ok() && updated.show_mode_indicator {
+                crate::show_mode_indicator(instance, &updated).await;
+            }
+            result
+        }
+        _ => execute_press(action, command, &CommandContext::new(instance, true), settings, dry_run_enabled()).await,
+    };
+    if result.is_ok()
+        && *action == DialPressAction::FavoriteSink
+        && let Some(title) = modes::executor(&ActionMode::SinkSwitch).title(settings).await
+    {
+        let _ = instance.set_title(Some(title), None).await;
+    }
+    match result {
+        Ok(()) => {
+            let _ = instance.show_ok().await;
+        }
+        Err(e) => {
+            if e.log("Press action failed") {
+                let _ = instance.show_alert().await;
+            }
+        }
+    }
+}
+
+/// Run a dial press action that only needs to spawn a command, or log it
+/// with `dry_run`
+async fn execute_press(
+    action: &DialPressAction,
+    command: &str,
+    context: &CommandContext,
+    settings: &RotateSettings,
+    dry_run: bool,
+) -> Result<(), ActionError> {
+    let press = async {
+        match action {
+            DialPressAction::None | DialPressAction::CycleMode => Ok(()),
+            DialPressAction::PlayPause => modes::executor(&settings.play_pause_target()).press(context, settings).await,
+            DialPressAction::MuteToggle => modes::executor(&settings.mute_target()).press(context, settings).await,
+            DialPressAction::FavoriteSink => modes::executor(&ActionMode::SinkSwitch).press(context, settings).await,
+            DialPressAction::ZoomReset => modes::executor(&ActionMode::Zoom).press(context, settings).await,
+            DialPressAction::PreviousWorkspace => modes::executor(&ActionMode::Workspace).press(context, settings).await,
+            DialPressAction::CloseTab => modes::executor(&ActionMode::TabSwitch).press(context, settings).await,
+            DialPressAction::Save => modes::executor(&ActionMode::UndoRedo).press(context, settings).await,
+            DialPressAction::Keys => modes::executor(&ActionMode::Keys).press(context, settings).await,
+            DialPressAction::ColorTempReset => modes::executor(&ActionMode::ColorTemp).press(context, settings).await,
+            DialPressAction::PlaybackRateReset => modes::executor(&ActionMode::PlaybackRate).press(context, settings).await,
+            DialPressAction::ObsTransition => modes::executor(&ActionMode::Obs).press(context, settings).await,
+            DialPressAction::HaToggle => modes::executor(&ActionMode::HomeAssistant).press(context, settings).await,
+            DialPressAction::Webhook => modes::executor(&ActionMode::Webhook).press(context, settings).await,
+            DialPressAction::Custom => {
+                if command.trim().is_empty() {
+                    return Err(ActionError::EmptyCommand);
+                }
+                log::info!("Custom press: {}", command);
+                run(custom_command(command, context, 0, 0, settings)?, settings).await
+            }
+        }
+    };
+    if dry_run {
+        log::info!("Dry run: {:?} press", action);
+        return DRY_RUN.scope(true, press).await;
+    }
+    press.await
+}
+
+/// Run a user-supplied command through `sh -c`, failing on a non-zero exit
+pub(crate) async fn run_shell(cmd: &str, settings: &RotateSettings) -> Result<(), ActionError> {
+    run(shell(cmd), settings).await
+}
+
+/// Run `cmd` to completion, failing on a non-zero exit
+pub(crate) async fn run(cmd: Command, settings: &RotateSettings) -> Result<(), ActionError> {
+    let program = program_name(&cmd);
+    let output = output(cmd, settings).await?;
+    if !output.status.success() {
+        return Err(ActionError::exit(&program, output.status.code(), &output.stderr));
+    }
+    Ok(())
+}
+
+/// The tool a command runs, looking through `sh -c` (`cmd /C` on Windows)
+/// to the first word of the script so a missing tool is named rather than the shell
+fn program_name(cmd: &Command) -> String {
+    let cmd = cmd.as_std();
+    let mut args = cmd.get_args();
+    if (cmd.get_program() == "sh" || cmd.get_program() == "cmd")
+        && args.next().is_some_and(|a| a == "-c" || a == "/C")
+        && let Some(word) = args.next().and_then(|script| script.to_str()).and_then(|s| s.split_whitespace().next())
+    {
+        return word.to_string();
+    }
+    cmd.get_program().to_string_lossy().into_owned()
+}
+
+/// Build a user command, through `sh -c` or split into argv per `use_shell`,
+/// with the action context in N1_DIRECTION (1, -1, or 0 for presses),
+/// N1_STEPS, N1_DEVICE_ID, N1_MODE and N1_PRESSED
+pub(crate) fn custom_command(cmd: &str, context: &CommandContext, direction: i8, steps: u32, settings: &RotateSettings) -> Result<Command, ActionError> {
+    // Tokenize even when the shell runs it, so unbalanced quotes are caught
+    // here with a clear message instead of as an opaque sh syntax error
+    let argv = shell_words::split(cmd).map_err(|e| ActionError::InvalidCommand(format!("{} in {:?}", e, cmd)))?;
+    let mut command = if settings.use_shell {
+        shell(cmd)
+    } else {
+        let (program, args) = argv.split_first().ok_or(ActionError::EmptyCommand)?;
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    };
+    command
+        .env("N1_DIRECTION", direction.to_string())
+        .env("N1_STEPS", steps.to_string())
+        .env("N1_DEVICE_ID", &context.device_id)
+        .env("N1_MODE", settings.mode().as_str())
+        .env("N1_PRESSED", if context.pressed { "1" } else { "0" });
+    Ok(command)
+}
+
+/// Whether `program` is an executable file in a PATH directory
+pub(crate) fn on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| is_executable(&dir.join(program)))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
+/// Quote `value` as a single shell word
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(not(windows))]
+pub(crate) fn shell(cmd: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd);
+    command
+}
+
+#[cfg(windows)]
+pub(crate) fn shell(cmd: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(cmd);
+    command
+}
+
+/// Spawn `cmd` and collect its output. If it outlives the configured
+/// timeout its whole process group is killed, so children of `sh -c` go
+/// too, and the timeout is reported with whatever stderr it produced.
+/// Reading the pipes shares the deadline: a command that exits but leaves
+/// a background child holding them returns with what was read by then.
+pub(crate) async fn output(mut cmd: Command, settings: &RotateSettings) -> Result<Output, ActionError> {
+    if dry_run() {
+        log::info!("Dry run, not executed: {:?}", cmd.as_std());
+        return Ok(Output {
+            status: ExitStatus::default(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+    }
+    let timeout = settings.command_timeout();
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = cmd.spawn().map_err(|e| ActionError::spawn(&program_name(&cmd), e))?;
+    let (mut stdout_pipe, mut stderr_pipe) = (child.stdout.take(), child.stderr.take());
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    // The pipes are drained while waiting so a chatty command cannot block
+    // on a full pipe; on timeout the buffers keep what was read so far
+    let finished = tokio::time::timeout(timeout, async {
+        let (status, _, _) = tokio::join!(
+            child.wait(),
+            read_all(&mut stdout_pipe, &mut stdout),
+            read_all(&mut stderr_pipe, &mut stderr),
+        );
+        status
+    })
+    .await;
+
+    let status = match finished {
+        Ok(status) => status.map_err(ActionError::Spawn)?,
+        Err(_) => match child.try_wait() {
+            Ok(Some(status)) => {
+                log::debug!("Command exited but its pipes stayed open past {:?}, left to run: {:?}", timeout, cmd.as_std());
+                status
+            }
+            _ => {
+                kill_process_group(&child);
+                let _ = child.kill().await;
+                log::warn!(
+                    "Command timed out after {:?} and was killed: {:?} (stderr: {})",
+                    timeout,
+                    cmd.as_std(),
+                    String::from_utf8_lossy(&stderr).trim()
+                );
+                return Err(ActionError::Timeout(timeout));
+            }
+        },
+    };
+    Ok(Output { status, stdout, stderr })
+}
+
+async fn read_all(pipe: &mut Option<impl AsyncRead + Unpin>, buf: &mut Vec<u8>) {
+    if let Some(pipe) = pipe {
+        let _ = pipe.read_to_end(buf).await;
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &Child) {
+    if let Some(pid) = child.id() {
+        // The child leads its own group (process_group(0)), so -pid reaches
+        // everything it spawned
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_child: &Child) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PluginState;
+    use openaction::async_trait;
+    use std::sync::Mutex;
+    #[cfg(target_os = "linux")]
+    use std::time::Instant;
+
+    #[cfg(target_os = "linux")]
+    fn settings(timeout_ms: u64) -> RotateSettings {
+        RotateSettings { command_timeout_ms: Some(timeout_ms), ..RotateSettings::default() }
+    }
+
+    /// Records the deltas it is asked to rotate by
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<i32>>);
+
+    #[async_trait]
+    impl ActionExecutor for Recorder {
+        async fn rotate(&self, delta: i32, _context: &CommandContext, _settings: &RotateSettings) -> Result<(), ActionError> {
+            self.0.lock().unwrap().push(delta);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn one_tick_rotates_once() {
+        let state = PluginState::default();
+        let settings = RotateSettings::default();
+        assert_eq!(settings.mode(), ActionMode::Volume);
+        let direction = settings.direction(1);
+        let steps = state.divide_ticks("dial", direction, 1, &settings);
+        let magnitude = state.rotation_magnitude("dial", steps, &settings);
+
+        let recorder = Recorder::default();
+        let context = CommandContext { device_id: "device".to_string(), pressed: false };
+        let feedback = rotate_with(&recorder, direction, magnitude, &context, &settings, false).await;
+        assert!(matches!(feedback, Ok(Feedback::Done)));
+        assert_eq!(*recorder.0.lock().unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn reversed_clockwise_tick_runs_the_ccw_command() {
+        let out = std::env::temp_dir().join(format!("n1-reverse-test-{}", std::process::id()));
+        let settings = RotateSettings {
+            mode: Some(ActionMode::Custom),
+            cw_command: format!("echo cw >> {}", out.display()),
+            ccw_command: format!("echo ccw >> {}", out.display()),
+            reverse: true,
+            ..settings(1000)
+        };
+        let context = CommandContext { device_id: "device".to_string(), pressed: false };
+        let result = execute_rotation(settings.direction(1), 1, &context, &settings, false).await;
+        let ran = std::fs::read_to_string(&out).unwrap_or_default();
+        let _ = std::fs::remove_file(&out);
+        assert!(result.is_ok());
+        assert_eq!(ran, "ccw\n");
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn custom_command_sees_the_context() {
+        let settings = RotateSettings { mode: Some(ActionMode::Custom), ..settings(1000) };
+        let context = CommandContext { device_id: "n1-device".to_string(), pressed: true };
+        let cmd = r#"printf '%s %s %s %s %s' "$N1_DIRECTION" "$N1_STEPS" "$N1_DEVICE_ID" "$N1_MODE" "$N1_PRESSED""#;
+        let shown = output(custom_command(cmd, &context, -1, 3, &settings).unwrap(), &settings).await.unwrap();
+        assert_eq!(String::from_utf8(shown.stdout).unwrap(), "-1 3 n1-device custom 1");
+
+        // Without the shell the variables are still in the environment
+        let settings = RotateSettings { use_shell: false, ..settings };
+        let context = CommandContext { pressed: false, ..context };
+        let shown = output(custom_command("env", &context, 1, 1, &settings).unwrap(), &settings).await.unwrap();
+        let env = String::from_utf8(shown.stdout).unwrap();
+        for var in ["N1_DIRECTION=1", "N1_STEPS=1", "N1_DEVICE_ID=n1-device", "N1_MODE=custom", "N1_PRESSED=0"] {
+            assert!(env.lines().any(|line| line == var), "{} missing from {}", var, env);
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn custom_command_without_a_shell_keeps_quoted_arguments() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let settings = RotateSettings { mode: Some(ActionMode::Custom), use_shell: false, ..settings(1000) };
+        let context = CommandContext { device_id: "device".to_string(), pressed: false };
+        let cmd = r#"printf '%s|' "a b" c '$N1_STEPS'"#;
+        let shown = output(custom_command(cmd, &context, 1, 1, &settings).unwrap(), &settings).await.unwrap();
+        assert_eq!(String::from_utf8(shown.stdout).unwrap(), "a b|c|$N1_STEPS|");
+
+        // A quoted program path may contain spaces
+        let dir = std::env::temp_dir().join(format!("n1 space test {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("my script");
+        std::fs::write(&script, "#!/bin/sh\nprintf '%s:%s' \"$#\" \"$1\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let cmd = format!("{} \"x y\"", shell_quote(&script.to_string_lossy()));
+        let shown = output(custom_command(&cmd, &context, 1, 1, &settings).unwrap(), &settings).await;
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(String::from_utf8(shown.unwrap().stdout).unwrap(), "1:x y");
+    }
+
+    /// Whether `pid` still runs; a zombie waiting for its parent does not
+    #[cfg(target_os = "linux")]
+    fn running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+            .is_ok_and(|stat| stat.rsplit(')').next().is_some_and(|rest| !rest.trim_start().starts_with('Z')))
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn timed_out_command_is_killed_with_its_children() {
+        let pid_file = std::env::temp_dir().join(format!("n1-timeout-test-{}", std::process::id()));
+        let script = format!("sleep 60 & echo $! > {}; wait", pid_file.display());
+        let start = Instant::now();
+        let result = output(shell(&script), &settings(300)).await;
+        assert!(matches!(result, Err(ActionError::Timeout(_))));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while running(&pid) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!running(&pid), "sleep {} outlived the timeout", pid.trim());
+
+        // Later commands still run
+        assert!(run(shell("true"), &settings(1000)).await.is_ok());
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn slow_command_does_not_block_another() {
+        let slow = tokio::spawn(async { output(shell("sleep 2"), &settings(5000)).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let start = Instant::now();
+        let shown = output(shell("echo hi"), &settings(5000)).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(String::from_utf8(shown.stdout).unwrap(), "hi\n");
+        assert!(!slow.is_finished());
+        slow.abort();
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn background_child_holding_the_pipes_does_not_hang() {
+        let start = Instant::now();
+        let output = output(shell("sleep 60 & echo $!"), &settings(300)).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(output.status.success());
+        let pid = String::from_utf8(output.stdout).unwrap();
+        assert!(running(&pid));
+        let _ = std::process::Command::new("kill").arg(pid.trim()).status();
+    }
+}
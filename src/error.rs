@@ -0,0 +1,231 @@
+/*!
+ * Errors returned by action executors
+ *
+ * Each variant is reported differently: a missing tool gets a one-time
+ * install hint, an empty command is only a configuration gap.
+ */
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// Exit status `sh` uses when it cannot find the command it was asked to run
+const SHELL_NOT_FOUND: i32 = 127;
+
+#[derive(Debug)]
+pub(crate) enum ActionError {
+    /// The external tool is not installed or not on `PATH`
+    CommandNotFound(String),
+    /// The command ran but exited unsuccessfully; `code` is unset when it was
+    /// killed by a signal
+    NonZeroExit { code: Option<i32>, stderr: String },
+    /// No command is configured for this direction or press
+    EmptyCommand,
+    /// The command could not be split into arguments, e.g. unbalanced quotes
+    InvalidCommand(String),
+    /// A Keys mode spec could not be parsed or sent with this backend
+    InvalidKeySpec(String),
+    /// The mode has no usable tool in this session
+    Unavailable(String),
+    /// The target cannot do what the mode asks, e.g. a player without rate
+    /// control; alerted once, then only logged
+    #[cfg(feature = "mpris")]
+    Unsupported(String),
+    /// The command could not be started
+    Spawn(io::Error),
+    /// The command outlived the timeout and was killed
+    Timeout(Duration),
+    /// Updating the instance settings failed
+    Settings(openaction::OpenActionError),
+    /// The ALSA mixer could not be opened or changed
+    #[cfg(feature = "alsa")]
+    Mixer(String),
+    /// An MPRIS player rejected a call
+    #[cfg(feature = "mpris")]
+    Mpris(String),
+    /// MPD answered ACK or the connection to it broke
+    Mpd(String),
+    /// Home Assistant answered with an error status or could not be parsed
+    #[cfg(feature = "home-assistant")]
+    HomeAssistant(String),
+    /// OBS refused a request or the connection to it broke
+    #[cfg(feature = "obs")]
+    Obs(String),
+    /// A webhook request failed or got a non-2xx reply
+    #[cfg(feature = "webhook")]
+    Webhook(String),
+    /// Writing to the uinput virtual device failed
+    #[cfg(all(feature = "uinput", target_os = "linux"))]
+    Uinput(io::Error),
+    /// A Windows audio or input call failed
+    #[cfg(windows)]
+    Windows(windows::core::Error),
+    /// A Quartz input event could not be created
+    #[cfg(target_os = "macos")]
+    Quartz(String),
+}
+
+impl ActionError {
+    /// Classify a failed exit, treating the shell's "not found" status as a
+    /// missing `program`
+    pub(crate) fn exit(program: &str, code: Option<i32>, stderr: &[u8]) -> Self {
+        if code == Some(SHELL_NOT_FOUND) {
+            return ActionError::CommandNotFound(program.to_string());
+        }
+        ActionError::NonZeroExit {
+            code,
+            stderr: String::from_utf8_lossy(stderr).trim().to_string(),
+        }
+    }
+
+    /// Classify a spawn error, mapping a missing executable to `CommandNotFound`
+    pub(crate) fn spawn(program: &str, err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            ActionError::CommandNotFound(program.to_string())
+        } else {
+            ActionError::Spawn(err)
+        }
+    }
+
+    /// Log the error at a level that fits the variant. Returns false for
+    /// errors that are not worth an alert on the key.
+    pub(crate) fn log(&self, context: &str) -> bool {
+        match self {
+            ActionError::EmptyCommand => {
+                log::debug!("{}: no command configured", context);
+                false
+            }
+            ActionError::CommandNotFound(program) => {
+                log::error!("{}: {}", context, self);
+                hint_install(program);
+                true
+            }
+            ActionError::Unavailable(reason) => {
+                if HINTED.lock().unwrap().insert(reason.clone()) {
+                    log::error!("{}: {}", context, reason);
+                } else {
+                    log::debug!("{}: {}", context, reason);
+                }
+                true
+            }
+            #[cfg(feature = "mpris")]
+            ActionError::Unsupported(reason) => {
+                if HINTED.lock().unwrap().insert(reason.clone()) {
+                    log::warn!("{}: {}", context, reason);
+                    true
+                } else {
+                    log::debug!("{}: {}", context, reason);
+                    false
+                }
+            }
+            ActionError::Timeout(_) => {
+                log::warn!("{}: {}", context, self);
+                true
+            }
+            _ => {
+                log::error!("{}: {}", context, self);
+                true
+            }
+        }
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::CommandNotFound(program) => write!(f, "command not found: {}", program),
+            ActionError::NonZeroExit { code: Some(code), stderr } => write!(f, "exited with status {}: {}", code, stderr),
+            ActionError::NonZeroExit { code: None, stderr } => write!(f, "terminated by signal: {}", stderr),
+            ActionError::EmptyCommand => write!(f, "empty command"),
+            ActionError::InvalidCommand(reason) => write!(f, "invalid command: {}", reason),
+            ActionError::InvalidKeySpec(reason) => write!(f, "invalid key spec: {}", reason),
+            ActionError::Unavailable(reason) => write!(f, "{}", reason),
+            #[cfg(feature = "mpris")]
+            ActionError::Unsupported(reason) => write!(f, "{}", reason),
+            ActionError::Spawn(e) => write!(f, "failed to start command: {}", e),
+            ActionError::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+            ActionError::Settings(e) => write!(f, "failed to update settings: {}", e),
+            #[cfg(feature = "alsa")]
+            ActionError::Mixer(e) => write!(f, "ALSA mixer: {}", e),
+            #[cfg(feature = "mpris")]
+            ActionError::Mpris(e) => write!(f, "MPRIS: {}", e),
+            ActionError::Mpd(e) => write!(f, "MPD: {}", e),
+            #[cfg(feature = "home-assistant")]
+            ActionError::HomeAssistant(e) => write!(f, "Home Assistant: {}", e),
+            #[cfg(feature = "obs")]
+            ActionError::Obs(e) => write!(f, "OBS: {}", e),
+            #[cfg(feature = "webhook")]
+            ActionError::Webhook(e) => write!(f, "webhook: {}", e),
+            #[cfg(all(feature = "uinput", target_os = "linux"))]
+            ActionError::Uinput(e) => write!(f, "uinput: {}", e),
+            #[cfg(windows)]
+            ActionError::Windows(e) => write!(f, "Windows: {}", e),
+            #[cfg(target_os = "macos")]
+            ActionError::Quartz(e) => write!(f, "Quartz: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ActionError::Spawn(e) => Some(e),
+            ActionError::Settings(e) => Some(e),
+            #[cfg(all(feature = "uinput", target_os = "linux"))]
+            ActionError::Uinput(e) => Some(e),
+            #[cfg(windows)]
+            ActionError::Windows(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<openaction::OpenActionError> for ActionError {
+    fn from(e: openaction::OpenActionError) -> Self {
+        ActionError::Settings(e)
+    }
+}
+
+#[cfg(windows)]
+impl From<windows::core::Error> for ActionError {
+    fn from(e: windows::core::Error) -> Self {
+        ActionError::Windows(e)
+    }
+}
+
+/// Tools and unavailable modes already reported, so a dial spun with a
+/// missing tool does not repeat the message on every tick
+static HINTED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+fn hint_install(program: &str) {
+    if !HINTED.lock().unwrap().insert(program.to_string()) {
+        return;
+    }
+    match package_for(program) {
+        Some(package) => log::warn!("'{}' is not installed; install the {} package (e.g. sudo apt install {})", program, package, package),
+        None => log::warn!("'{}' was not found; check that it is installed and on PATH", program),
+    }
+}
+
+/// Debian/Ubuntu package providing a tool the built-in modes use
+fn package_for(program: &str) -> Option<&'static str> {
+    match program {
+        "amixer" => Some("alsa-utils"),
+        "wpctl" => Some("wireplumber"),
+        "pactl" => Some("pulseaudio-utils"),
+        "playerctl" => Some("playerctl"),
+        "xdotool" => Some("xdotool"),
+        "ydotool" => Some("ydotool"),
+        "brightnessctl" => Some("brightnessctl"),
+        "ddcutil" => Some("ddcutil"),
+        "gammastep" => Some("gammastep"),
+        "redshift" => Some("redshift"),
+        "wlsunset" => Some("wlsunset"),
+        "swaymsg" => Some("sway"),
+        "i3-msg" => Some("i3-wm"),
+        "hyprctl" => Some("hyprland"),
+        _ => None,
+    }
+}
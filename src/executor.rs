@@ -0,0 +1,147 @@
+/*!
+ * Single-flight rotation executor
+ *
+ * Each mode that can merge rotations gets one task fed by a channel. While
+ * its command runs, further rotations wait in the channel; when it finishes
+ * they are collapsed into one follow-up command per instance, so a fast spin
+ * never leaves a backlog of stale amixer calls behind. A slot also enforces
+ * `rate_limit_ms` between executions, holding rotations back meanwhile.
+ */
+
+use crate::commands::{CommandContext, dry_run_enabled, execute_rotation, report_rotation, spawn_rotation};
+use crate::{ActionMode, RotateSettings};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// A rotation waiting for its mode's slot; `steps` is signed by direction
+struct Rotation {
+    instance_id: String,
+    context: CommandContext,
+    steps: i64,
+    settings: RotateSettings,
+}
+
+/// Channel into each mode's executor task, created on first use
+static SLOTS: LazyLock<Mutex<HashMap<ActionMode, UnboundedSender<Rotation>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Run `steps` signed steps for an instance. Modes where every event matters
+/// run straight away unless rate limited; the others queue behind their
+/// mode's running command.
+pub(crate) fn submit(instance_id: String, context: CommandContext, steps: i64, settings: RotateSettings) {
+    if steps == 0 {
+        return;
+    }
+    if !settings.mode().single_flight() && settings.rate_limit_ms == 0 {
+        let (direction, magnitude) = split_steps(steps);
+        spawn_rotation(instance_id, context, direction, magnitude, settings);
+        return;
+    }
+
+    let mode = settings.mode();
+    let rotation = Rotation { instance_id, context, steps, settings };
+    let mut slots = SLOTS.lock().unwrap();
+    let slot = slots.entry(mode).or_insert_with(|| {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_slot(rx));
+        tx
+    });
+    if let Err(mpsc::error::SendError(rotation)) = slot.send(rotation) {
+        // Only possible if the task panicked; start a fresh one
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_slot(rx));
+        let _ = tx.send(rotation);
+        *slot = tx;
+    }
+}
+
+async fn run_slot(mut rx: UnboundedReceiver<Rotation>) {
+    let mut last_run: Option<Instant> = None;
+    while let Some(first) = rx.recv().await {
+        let mut batch = vec![first];
+        drain(&mut rx, &mut batch);
+
+        let interval = batch.iter().map(|r| r.settings.rate_limit_ms).max().unwrap_or(0);
+        let wait = last_run.map_or(Duration::ZERO, |last| {
+            Duration::from_millis(interval).saturating_sub(last.elapsed())
+        });
+        if !wait.is_zero() {
+            log::debug!("Rate limit: holding {:?} rotations for {:?}", batch[0].settings.mode(), wait);
+            tokio::time::sleep(wait).await;
+            drain(&mut rx, &mut batch);
+        }
+
+        last_run = Some(Instant::now());
+        for rotation in batch {
+            run(rotation).await;
+        }
+    }
+}
+
+fn drain(rx: &mut UnboundedReceiver<Rotation>, batch: &mut Vec<Rotation>) {
+    while let Ok(next) = rx.try_recv() {
+        merge(batch, next);
+    }
+}
+
+/// Fold a queued rotation into the pending one for the same instance,
+/// keeping its latest settings
+fn merge(batch: &mut Vec<Rotation>, next: Rotation) {
+    match batch.iter_mut().find(|r| r.instance_id == next.instance_id) {
+        Some(pending) => {
+            pending.steps += next.steps;
+            pending.context = next.context;
+            pending.settings = next.settings;
+        }
+        None => batch.push(next),
+    }
+}
+
+async fn run(rotation: Rotation) {
+    if rotation.steps == 0 {
+        log::debug!("Queued rotation cancelled out: {}", rotation.instance_id);
+        return;
+    }
+    let (direction, magnitude) = split_steps(rotation.steps);
+    let result = execute_rotation(direction, magnitude, &rotation.context, &rotation.settings, dry_run_enabled()).await;
+    if let Some(instance) = openaction::get_instance(rotation.instance_id).await {
+        report_rotation(&instance, result, &rotation.settings).await;
+    }
+}
+
+fn split_steps(steps: i64) -> (i8, u32) {
+    let direction = if steps > 0 { 1 } else { -1 };
+    (direction, u32::try_from(steps.unsigned_abs()).unwrap_or(u32::MAX))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_custom_rotation_does_not_hold_up_the_next() {
+        let out = std::env::temp_dir().join(format!("n1-slow-custom-test-{}", std::process::id()));
+        let settings = RotateSettings {
+            mode: Some(ActionMode::Custom),
+            cw_command: "sleep 2".to_string(),
+            ccw_command: format!("echo ccw >> {}", out.display()),
+            ..RotateSettings::default()
+        };
+        let context = || CommandContext { device_id: "device".to_string(), pressed: false };
+
+        let started = Instant::now();
+        submit("slow".to_string(), context(), 1, settings.clone());
+        submit("fast".to_string(), context(), -1, settings);
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        while !out.exists() && started.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let ran = std::fs::read_to_string(&out).unwrap_or_default();
+        let _ = std::fs::remove_file(&out);
+        assert_eq!(ran, "ccw\n");
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
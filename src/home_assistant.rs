@@ -0,0 +1,82 @@
+/*!
+ * Home Assistant over its REST API
+ *
+ * Authenticated with a long-lived access token (Profile → Security in Home
+ * Assistant). Local installs with a self-signed certificate need
+ * `ha_insecure`, which skips certificate checks for that dial only.
+ */
+
+use crate::error::ActionError;
+use serde_json::Value;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// How long one request may take, kept short so a stalled server does not
+/// hold up the dial
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Clients are reused for their connection pool, one per certificate policy
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| client(false));
+static INSECURE_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| client(true));
+
+fn client(insecure: bool) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .danger_accept_invalid_certs(insecure)
+        .build()
+        .unwrap_or_else(|e| {
+            log::warn!("Home Assistant: falling back to the default HTTP client: {}", e);
+            reqwest::Client::new()
+        })
+}
+
+/// Where Home Assistant is and how to sign in, from the dial's settings
+pub(crate) struct Server {
+    /// Base URL without a trailing slash, e.g. "http://homeassistant.local:8123"
+    pub(crate) url: String,
+    pub(crate) token: String,
+    pub(crate) insecure: bool,
+}
+
+impl Server {
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder, ActionError> {
+        if self.url.is_empty() || self.token.is_empty() {
+            return Err(ActionError::HomeAssistant("set ha_url and ha_token".to_string()));
+        }
+        let client = if self.insecure { &*INSECURE_CLIENT } else { &*CLIENT };
+        Ok(client.request(method, format!("{}{}", self.url, path)).bearer_auth(&self.token))
+    }
+}
+
+/// Call `domain.service`, e.g. light.turn_on, with `data`
+pub(crate) async fn call_service(server: &Server, domain: &str, service: &str, data: Value) -> Result<(), ActionError> {
+    let request = server.request(reqwest::Method::POST, &format!("/api/services/{}/{}", domain, service))?;
+    send(request.json(&data), &format!("{}.{}", domain, service)).await?;
+    Ok(())
+}
+
+/// Current state object of `entity`, with its attributes
+pub(crate) async fn state(server: &Server, entity: &str) -> Result<Value, ActionError> {
+    let request = server.request(reqwest::Method::GET, &format!("/api/states/{}", entity))?;
+    let body = send(request, entity).await?;
+    serde_json::from_str(&body).map_err(|e| ActionError::HomeAssistant(format!("{}: {}", entity, e)))
+}
+
+/// Send a request and return the body, turning HTTP errors into ones that
+/// name the status
+async fn send(request: reqwest::RequestBuilder, what: &str) -> Result<String, ActionError> {
+    let response = request.send().await.map_err(|e| {
+        if e.is_connect() || e.is_timeout() {
+            log::debug!("Home Assistant: {}: {}", what, e.without_url());
+            ActionError::Unavailable("Home Assistant is not reachable; check ha_url".to_string())
+        } else {
+            ActionError::HomeAssistant(format!("{}: {}", what, e.without_url()))
+        }
+    })?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(ActionError::HomeAssistant(format!("{}: HTTP {} {}", what, status, body.trim())));
+    }
+    Ok(body)
+}
@@ -0,0 +1,244 @@
+/*!
+ * Key specs for Keys mode
+ *
+ * A spec is one or more chords separated by spaces, sent in order. A chord
+ * is `+`-joined modifiers ending in one key, spelled as xdotool spells them:
+ * "ctrl+shift+Right", "XF86AudioNext", "ctrl+c ctrl+v".
+ */
+
+use crate::error::ActionError;
+
+/// One parsed chord
+pub(crate) struct KeyChord {
+    /// The chord as written, which is what xdotool takes
+    pub(crate) keysym: String,
+    /// Linux input event codes, modifiers first, for ydotool, uinput and
+    /// SendInput. None when the key is not in `KEYS`; xdotool may still
+    /// know it.
+    pub(crate) codes: Option<Vec<u16>>,
+}
+
+impl KeyChord {
+    /// The input codes, or an error naming the key for backends that need them
+    pub(crate) fn codes(&self, backend: &str) -> Result<&[u16], ActionError> {
+        self.codes.as_deref().ok_or_else(|| {
+            ActionError::InvalidKeySpec(format!("{} does not know the key in {:?}; xdotool may", backend, self.keysym))
+        })
+    }
+}
+
+/// Modifier names and their codes
+const MODIFIERS: &[(&str, u16)] = &[
+    ("ctrl", 29),
+    ("control", 29),
+    ("control_l", 29),
+    ("shift", 42),
+    ("shift_l", 42),
+    ("alt", 56),
+    ("alt_l", 56),
+    ("super", 125),
+    ("super_l", 125),
+    ("meta", 125),
+    ("win", 125),
+];
+
+/// Key names, lower case, and their Linux input event codes
+pub(crate) const KEYS: &[(&str, u16)] = &[
+    ("escape", 1),
+    ("esc", 1),
+    ("1", 2),
+    ("2", 3),
+    ("3", 4),
+    ("4", 5),
+    ("5", 6),
+    ("6", 7),
+    ("7", 8),
+    ("8", 9),
+    ("9", 10),
+    ("0", 11),
+    ("minus", 12),
+    ("equal", 13),
+    ("backspace", 14),
+    ("tab", 15),
+    ("q", 16),
+    ("w", 17),
+    ("e", 18),
+    ("r", 19),
+    ("t", 20),
+    ("y", 21),
+    ("u", 22),
+    ("i", 23),
+    ("o", 24),
+    ("p", 25),
+    ("bracketleft", 26),
+    ("bracketright", 27),
+    ("return", 28),
+    ("enter", 28),
+    ("a", 30),
+    ("s", 31),
+    ("d", 32),
+    ("f", 33),
+    ("g", 34),
+    ("h", 35),
+    ("j", 36),
+    ("k", 37),
+    ("l", 38),
+    ("semicolon", 39),
+    ("apostrophe", 40),
+    ("grave", 41),
+    ("backslash", 43),
+    ("z", 44),
+    ("x", 45),
+    ("c", 46),
+    ("v", 47),
+    ("b", 48),
+    ("n", 49),
+    ("m", 50),
+    ("comma", 51),
+    ("period", 52),
+    ("slash", 53),
+    ("space", 57),
+    ("caps_lock", 58),
+    ("f1", 59),
+    ("f2", 60),
+    ("f3", 61),
+    ("f4", 62),
+    ("f5", 63),
+    ("f6", 64),
+    ("f7", 65),
+    ("f8", 66),
+    ("f9", 67),
+    ("f10", 68),
+    ("f11", 87),
+    ("f12", 88),
+    ("print", 99),
+    ("home", 102),
+    ("up", 103),
+    ("prior", 104),
+    ("page_up", 104),
+    ("left", 105),
+    ("right", 106),
+    ("end", 107),
+    ("down", 108),
+    ("next", 109),
+    ("page_down", 109),
+    ("insert", 110),
+    ("delete", 111),
+    ("xf86audiomute", 113),
+    ("xf86audiolowervolume", 114),
+    ("xf86audioraisevolume", 115),
+    ("pause", 119),
+    ("menu", 127),
+    ("xf86audionext", 163),
+    ("xf86audioplay", 164),
+    ("xf86audioprev", 165),
+    ("xf86audiostop", 166),
+    ("f13", 183),
+    ("f14", 184),
+    ("f15", 185),
+    ("f16", 186),
+    ("f17", 187),
+    ("f18", 188),
+    ("f19", 189),
+    ("f20", 190),
+    ("f21", 191),
+    ("f22", 192),
+    ("f23", 193),
+    ("f24", 194),
+    ("xf86monbrightnessdown", 224),
+    ("xf86monbrightnessup", 225),
+];
+
+fn lookup(table: &[(&str, u16)], name: &str) -> Option<u16> {
+    let name = name.to_ascii_lowercase();
+    table.iter().find(|(known, _)| *known == name).map(|(_, code)| *code)
+}
+
+/// Codes for the key at the end of a chord. A single upper case letter is
+/// Shift plus the letter, as in xdotool; modifiers may stand alone.
+fn key_codes(key: &str) -> Option<Vec<u16>> {
+    if key.len() == 1 && key.chars().all(|c| c.is_ascii_uppercase()) {
+        return lookup(KEYS, key).map(|code| vec![42, code]);
+    }
+    lookup(KEYS, key).or_else(|| lookup(MODIFIERS, key)).map(|code| vec![code])
+}
+
+/// Parse a space-separated list of chords. Anything but a modifier before
+/// the last `+` is an error; a key missing from the code table is not, as
+/// xdotool can still send it.
+pub(crate) fn parse(spec: &str) -> Result<Vec<KeyChord>, ActionError> {
+    let chords = spec.split_whitespace().map(|chord| {
+        let mut parts: Vec<&str> = chord.split('+').collect();
+        let key = parts.pop().unwrap_or_default();
+        if key.is_empty() || parts.iter().any(|part| part.is_empty()) {
+            return Err(ActionError::InvalidKeySpec(format!("empty key in {:?}", chord)));
+        }
+        let mut codes = Vec::with_capacity(parts.len() + 1);
+        for part in parts {
+            let code = lookup(MODIFIERS, part)
+                .ok_or_else(|| ActionError::InvalidKeySpec(format!("{:?} is not a modifier in {:?}", part, chord)))?;
+            codes.push(code);
+        }
+        let codes = key_codes(key).map(|key| {
+            codes.extend(key);
+            codes
+        });
+        Ok(KeyChord { keysym: chord.to_string(), codes })
+    });
+    let chords = chords.collect::<Result<Vec<_>, _>>()?;
+    if chords.is_empty() {
+        return Err(ActionError::EmptyCommand);
+    }
+    Ok(chords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(spec: &str) -> Vec<Option<Vec<u16>>> {
+        parse(spec).unwrap().into_iter().map(|chord| chord.codes).collect()
+    }
+
+    #[test]
+    fn modifier_chord() {
+        let chords = parse("ctrl+shift+Right").unwrap();
+        assert_eq!(chords.len(), 1);
+        assert_eq!(chords[0].keysym, "ctrl+shift+Right");
+        assert_eq!(chords[0].codes, Some(vec![29, 42, 106]));
+    }
+
+    #[test]
+    fn chords_are_sent_in_order() {
+        assert_eq!(codes("ctrl+c ctrl+v"), vec![Some(vec![29, 46]), Some(vec![29, 47])]);
+    }
+
+    #[test]
+    fn xf86_keys() {
+        assert_eq!(codes("XF86AudioNext"), vec![Some(vec![163])]);
+        assert_eq!(codes("XF86MonBrightnessUp"), vec![Some(vec![225])]);
+    }
+
+    #[test]
+    fn upper_case_letter_is_shifted() {
+        assert_eq!(codes("A"), vec![Some(vec![42, 30])]);
+        assert_eq!(codes("a"), vec![Some(vec![30])]);
+        assert_eq!(codes("ctrl+T"), vec![Some(vec![29, 42, 20])]);
+    }
+
+    #[test]
+    fn unknown_key_is_left_to_xdotool() {
+        let chords = parse("ctrl+XF86Calculator").unwrap();
+        assert_eq!(chords[0].codes, None);
+        assert!(matches!(chords[0].codes("ydotool"), Err(ActionError::InvalidKeySpec(_))));
+    }
+
+    #[test]
+    fn rejected_specs() {
+        assert!(matches!(parse("foo+x"), Err(ActionError::InvalidKeySpec(_))));
+        assert!(matches!(parse("ctrl+"), Err(ActionError::InvalidKeySpec(_))));
+        assert!(matches!(parse("ctrl++x"), Err(ActionError::InvalidKeySpec(_))));
+        assert!(matches!(parse(""), Err(ActionError::EmptyCommand)));
+        assert!(matches!(parse("   "), Err(ActionError::EmptyCommand)));
+    }
+}
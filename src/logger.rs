@@ -0,0 +1,56 @@
+/*!
+ * Logger that keeps fast spins from flooding the log
+ *
+ * Wraps the terminal logger. While a rotation runs in a quiet scope, which
+ * `execute_rotation` opens for all but the first rotation of a burst, its
+ * info lines are demoted to debug. Warnings and errors always go out.
+ */
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use simplelog::{ColorChoice, Config, TermLogger, TerminalMode};
+use std::future::Future;
+
+tokio::task_local! {
+    /// Whether the rotation running on this task logs its info lines at debug
+    static QUIET: bool;
+}
+
+struct Throttled(Box<TermLogger>);
+
+impl Log for Throttled {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() != Level::Info || !QUIET.try_with(|quiet| *quiet).unwrap_or(false) {
+            return self.0.log(record);
+        }
+        let demoted = Record::builder()
+            .args(*record.args())
+            .level(Level::Debug)
+            .target(record.target())
+            .module_path(record.module_path())
+            .file(record.file())
+            .line(record.line())
+            .build();
+        self.0.log(&demoted);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+/// Install the logger, writing to stdout at `level`
+pub(crate) fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+    let inner = TermLogger::new(level, Config::default(), TerminalMode::Stdout, ColorChoice::Never);
+    log::set_boxed_logger(Box::new(Throttled(inner)))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Run `future` with its info lines demoted to debug if `quiet`
+pub(crate) async fn quiet<F: Future>(quiet: bool, future: F) -> F::Output {
+    QUIET.scope(quiet, future).await
+}
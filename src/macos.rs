@@ -0,0 +1,61 @@
+/*!
+ * macOS backends for media keys and Scroll
+ *
+ * Media keys are posted as the system-defined events the keyboard's own
+ * play/next/previous keys produce, so whichever player owns Now Playing
+ * reacts. The wheel goes through a Quartz scroll event. Posting either needs
+ * the Accessibility permission for OpenDeck; without it macOS drops the
+ * events silently.
+ */
+
+use crate::error::ActionError;
+use crate::modes::Axis;
+use objc2_app_kit::{NSEvent, NSEventModifierFlags, NSEventType};
+use objc2_core_graphics::{CGEvent, CGEventTapLocation, CGScrollEventUnit};
+use objc2_foundation::NSPoint;
+
+/// NX_KEYTYPE_* codes from IOKit's ev_keymap.h
+#[derive(Clone, Copy)]
+pub(crate) enum MediaKey {
+    PlayPause = 16,
+    Next = 17,
+    Previous = 18,
+}
+
+/// Subtype of the system-defined events that carry auxiliary keys
+const AUX_CONTROL_BUTTONS: i16 = 8;
+
+/// Press and release a media key
+pub(crate) fn media_key(key: MediaKey) -> Result<(), ActionError> {
+    // data1 is the key code in the high 16 bits, then 0xa (down) or 0xb
+    // (up) in the next nibble
+    for state in [0xa00, 0xb00] {
+        let event = NSEvent::otherEventWithType_location_modifierFlags_timestamp_windowNumber_context_subtype_data1_data2(
+            NSEventType::SystemDefined,
+            NSPoint::new(0.0, 0.0),
+            NSEventModifierFlags(state),
+            0.0,
+            0,
+            None,
+            AUX_CONTROL_BUTTONS,
+            ((key as isize) << 16) | state as isize,
+            -1,
+        )
+        .and_then(|event| event.CGEvent())
+        .ok_or_else(|| ActionError::Quartz("cannot create media key event".to_string()))?;
+        CGEvent::post(CGEventTapLocation::HIDEventTap, Some(&event));
+    }
+    Ok(())
+}
+
+/// Scroll the wheel on `axis` by `clicks` lines; positive scrolls up or
+/// right. Quartz counts the second wheel positive to the left.
+pub(crate) fn scroll(axis: Axis, clicks: i32) -> Result<(), ActionError> {
+    let event = match axis {
+        Axis::Vertical => CGEvent::new_scroll_wheel_event2(None, CGScrollEventUnit::Line, 1, clicks, 0, 0),
+        Axis::Horizontal => CGEvent::new_scroll_wheel_event2(None, CGScrollEventUnit::Line, 2, 0, -clicks, 0),
+    }
+    .ok_or_else(|| ActionError::Quartz("cannot create scroll event".to_string()))?;
+    CGEvent::post(CGEventTapLocation::HIDEventTap, Some(&event));
+    Ok(())
+}
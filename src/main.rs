@@ -1,4 +1,4 @@
-/**
+/*!
  * N1 Encoder Actions - OpenDeck Plugin
  * 
  * Provides configurable multi-action support for the Ajazz N1 encoder dial.
@@ -7,57 +7,1116 @@
 use openaction::{
     Action, Instance, OpenActionResult,
     async_trait,
-    global_events::GlobalEventHandler,
+    global_events::{DeviceDidConnectEvent, DeviceDidDisconnectEvent, DidReceiveGlobalSettingsEvent, GlobalEventHandler},
 };
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::sync::LazyLock;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+mod commands;
+mod error;
+mod executor;
+#[cfg(feature = "home-assistant")]
+mod home_assistant;
+mod keyspec;
+mod logger;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(feature = "alsa")]
+mod mixer;
+mod modes;
+mod mpd;
+#[cfg(feature = "mpris")]
+mod mpris;
+#[cfg(feature = "obs")]
+mod obs;
+#[cfg(all(feature = "uinput", target_os = "linux"))]
+mod uinput;
+mod state;
+#[cfg(unix)]
+mod status;
+#[cfg(feature = "webhook")]
+mod webhook;
+#[cfg(windows)]
+mod win32;
+
+use commands::{CommandContext, run_press_action, spawn_press_action};
+use state::STATE;
+
+/// Plugin-wide command timeout, overridable with N1_COMMAND_TIMEOUT_MS
+static DEFAULT_COMMAND_TIMEOUT_MS: LazyLock<u64> = LazyLock::new(|| {
+    std::env::var("N1_COMMAND_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2000)
+});
 
 // Action UUIDs from manifest.json
 const ACTION_ROTATE_UUID: &str = "net.ashurtech.n1-encoder-actions.rotate";
 const ACTION_PRESS_UUID: &str = "net.ashurtech.n1-encoder-actions.press";
 
+/// Shape of the stored settings this build writes, see `RotateSettings::migrate`
+const SETTINGS_VERSION: u32 = 1;
+
 /// Action mode - what the encoder does when rotated
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
 #[serde(rename_all = "snake_case")]
 enum ActionMode {
+    #[default]
     Volume,
+    /// Volume of the default microphone
+    MicVolume,
+    /// Cycle the default audio output through the available sinks
+    SinkSwitch,
     MediaTrack,
     MediaSeek,
+    /// MPRIS playback rate of the active player
+    PlaybackRate,
+    /// Pick the player the media modes control
+    PlayerSelect,
     Scroll,
+    /// Horizontal wheel, e.g. for timelines
+    HScroll,
     Brightness,
+    /// External monitor brightness over DDC/CI
+    MonitorBrightness,
+    /// Screen color temperature through gammastep, redshift or wlsunset
+    ColorTemp,
+    Zoom,
+    /// Next/previous workspace in sway, i3 or Hyprland
+    Workspace,
+    /// Alt+Tab with Alt held between ticks
+    WindowSwitch,
+    /// Next/previous tab in browsers, editors and terminals
+    TabSwitch,
+    /// Ctrl+Z / Ctrl+Shift+Z, one per detent
+    UndoRedo,
+    /// Nudge the mouse pointer along `mouse_axis`
+    MouseMove,
+    /// Right/Left arrow keys, or `cw_keys`/`ccw_keys` when set
+    #[serde(alias = "arrow_nav")]
+    Pan,
+    /// Key specs per direction, e.g. "ctrl+shift+Right" or "ctrl+c ctrl+v"
+    #[serde(alias = "key_press")]
+    Keys,
+    /// OBS Studio source volume or scene, over obs-websocket
+    Obs,
+    /// MPD volume or seek over its own protocol
+    Mpd,
+    /// Home Assistant light brightness over the REST API
+    HomeAssistant,
+    /// An HTTP request per rotation to `webhook_url`
+    Webhook,
     Custom,
 }
 
-impl Default for ActionMode {
-    fn default() -> Self {
-        ActionMode::Volume
+/// Tool used to change the output volume in Volume mode
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum VolumeBackend {
+    /// wpctl, pactl or amixer, whichever is found first on PATH
+    #[default]
+    Auto,
+    Amixer,
+    Wpctl,
+    Pactl,
+    /// In-process ALSA mixer; needs the `alsa` cargo feature, otherwise amixer
+    Alsa,
+    /// Core Audio endpoint volume, what Auto picks on Windows
+    Windows,
+    /// AppleScript `set volume`, what Auto picks on macOS
+    Osascript,
+}
+
+/// Backend `VolumeBackend::Auto` stands for, probed once at startup
+static DETECTED_VOLUME_BACKEND: LazyLock<VolumeBackend> = LazyLock::new(|| {
+    let capabilities = STATE.capabilities();
+    let backend = if cfg!(windows) {
+        VolumeBackend::Windows
+    } else if cfg!(target_os = "macos") && capabilities.has("osascript") {
+        VolumeBackend::Osascript
+    } else if capabilities.has("wpctl") {
+        VolumeBackend::Wpctl
+    } else if capabilities.has("pactl") {
+        VolumeBackend::Pactl
+    } else {
+        VolumeBackend::Amixer
+    };
+    log::info!("Auto volume backend: {:?}", backend);
+    backend
+});
+
+impl VolumeBackend {
+    /// The concrete backend to run, resolving Auto
+    fn resolve(&self) -> VolumeBackend {
+        match self {
+            VolumeBackend::Auto => DETECTED_VOLUME_BACKEND.clone(),
+            #[cfg(not(feature = "alsa"))]
+            VolumeBackend::Alsa => {
+                static WARNED: std::sync::Once = std::sync::Once::new();
+                WARNED.call_once(|| log::warn!("Built without the alsa feature; using amixer for the alsa backend"));
+                VolumeBackend::Amixer
+            }
+            #[cfg(not(windows))]
+            VolumeBackend::Windows => DETECTED_VOLUME_BACKEND.clone(),
+            backend => backend.clone(),
+        }
+    }
+}
+
+impl ActionMode {
+    /// Whether fast spins may multiply the step. Skipping several tracks
+    /// per detent is never wanted.
+    fn supports_acceleration(&self) -> bool {
+        !matches!(self, ActionMode::MediaTrack | ActionMode::SinkSwitch | ActionMode::PlayerSelect | ActionMode::Workspace | ActionMode::WindowSwitch | ActionMode::TabSwitch | ActionMode::UndoRedo | ActionMode::Keys | ActionMode::Obs | ActionMode::Webhook | ActionMode::Custom)
+    }
+
+    /// Whether rotations queued behind a running command may be collapsed
+    /// into one. Track skips, undo steps, key presses and custom commands
+    /// run per event.
+    fn single_flight(&self) -> bool {
+        !matches!(self, ActionMode::MediaTrack | ActionMode::UndoRedo | ActionMode::Keys | ActionMode::Custom)
+    }
+
+    /// Whether ticks are collected over `coalesce_ms` first. Undo and redo
+    /// go out as they come, N presses for N ticks, so a reversal mid-spin
+    /// redoes what was just undone instead of cancelling out.
+    fn coalesces(&self) -> bool {
+        *self != ActionMode::UndoRedo
+    }
+
+    /// The mode after this one, wrapping around, for CycleMode presses
+    fn next(&self) -> ActionMode {
+        match self {
+            ActionMode::Volume => ActionMode::MicVolume,
+            ActionMode::MicVolume => ActionMode::SinkSwitch,
+            ActionMode::SinkSwitch => ActionMode::MediaTrack,
+            ActionMode::MediaTrack => ActionMode::MediaSeek,
+            ActionMode::MediaSeek => ActionMode::PlaybackRate,
+            ActionMode::PlaybackRate => ActionMode::PlayerSelect,
+            ActionMode::PlayerSelect => ActionMode::Scroll,
+            ActionMode::Scroll => ActionMode::HScroll,
+            ActionMode::HScroll => ActionMode::MouseMove,
+            ActionMode::MouseMove => ActionMode::Brightness,
+            ActionMode::Brightness => ActionMode::MonitorBrightness,
+            ActionMode::MonitorBrightness => ActionMode::ColorTemp,
+            ActionMode::ColorTemp => ActionMode::Zoom,
+            ActionMode::Zoom => ActionMode::Workspace,
+            ActionMode::Workspace => ActionMode::WindowSwitch,
+            ActionMode::WindowSwitch => ActionMode::TabSwitch,
+            ActionMode::TabSwitch => ActionMode::UndoRedo,
+            ActionMode::UndoRedo => ActionMode::Pan,
+            ActionMode::Pan => ActionMode::Keys,
+            ActionMode::Keys => ActionMode::Obs,
+            ActionMode::Obs => ActionMode::Mpd,
+            ActionMode::Mpd => ActionMode::HomeAssistant,
+            ActionMode::HomeAssistant => ActionMode::Webhook,
+            ActionMode::Webhook => ActionMode::Custom,
+            ActionMode::Custom => ActionMode::Volume,
+        }
+    }
+
+    /// Short name for the mode indicator on the dial's display
+    fn title(&self) -> &'static str {
+        match self {
+            ActionMode::Volume => "Volume",
+            ActionMode::MicVolume => "Mic",
+            ActionMode::SinkSwitch => "Output",
+            ActionMode::MediaTrack => "Track",
+            ActionMode::MediaSeek => "Seek",
+            ActionMode::PlaybackRate => "Speed",
+            ActionMode::PlayerSelect => "Player",
+            ActionMode::Scroll => "Scroll",
+            ActionMode::HScroll => "H-Scroll",
+            ActionMode::MouseMove => "Mouse",
+            ActionMode::Brightness => "Brightness",
+            ActionMode::MonitorBrightness => "Monitor",
+            ActionMode::ColorTemp => "Color Temp",
+            ActionMode::Zoom => "Zoom",
+            ActionMode::Workspace => "Workspace",
+            ActionMode::WindowSwitch => "Windows",
+            ActionMode::TabSwitch => "Tabs",
+            ActionMode::UndoRedo => "Undo",
+            ActionMode::Pan => "Pan",
+            ActionMode::Keys => "Keys",
+            ActionMode::Obs => "OBS",
+            ActionMode::Mpd => "MPD",
+            ActionMode::HomeAssistant => "Light",
+            ActionMode::Webhook => "Webhook",
+            ActionMode::Custom => "Custom",
+        }
+    }
+
+    /// Settings name of the mode, as passed to custom commands in N1_MODE
+    fn as_str(&self) -> &'static str {
+        match self {
+            ActionMode::Volume => "volume",
+            ActionMode::MicVolume => "mic_volume",
+            ActionMode::SinkSwitch => "sink_switch",
+            ActionMode::MediaTrack => "media_track",
+            ActionMode::MediaSeek => "media_seek",
+            ActionMode::PlaybackRate => "playback_rate",
+            ActionMode::PlayerSelect => "player_select",
+            ActionMode::Scroll => "scroll",
+            ActionMode::HScroll => "hscroll",
+            ActionMode::MouseMove => "mouse_move",
+            ActionMode::Brightness => "brightness",
+            ActionMode::MonitorBrightness => "monitor_brightness",
+            ActionMode::ColorTemp => "color_temp",
+            ActionMode::Zoom => "zoom",
+            ActionMode::Workspace => "workspace",
+            ActionMode::WindowSwitch => "window_switch",
+            ActionMode::TabSwitch => "tab_switch",
+            ActionMode::UndoRedo => "undo_redo",
+            ActionMode::Pan => "pan",
+            ActionMode::Keys => "keys",
+            ActionMode::Obs => "obs",
+            ActionMode::Mpd => "mpd",
+            ActionMode::HomeAssistant => "home_assistant",
+            ActionMode::Webhook => "webhook",
+            ActionMode::Custom => "custom",
+        }
+    }
+}
+
+/// What pressing the dial does locally
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum DialPressAction {
+    #[default]
+    None,
+    #[serde(alias = "media_play_pause")]
+    PlayPause,
+    #[serde(alias = "mute")]
+    MuteToggle,
+    /// Switch the audio output back to `favorite_sink`
+    FavoriteSink,
+    /// Ctrl+0, resetting the zoom of the focused application
+    ZoomReset,
+    /// Go back to the previously focused workspace
+    PreviousWorkspace,
+    /// Close the current tab with the `tab_keys` binding (Ctrl+W)
+    CloseTab,
+    /// Ctrl+S, saving the document in the focused application
+    Save,
+    /// Send `press_keys`
+    Keys,
+    /// Set the screen color temperature back to 6500 K
+    ColorTempReset,
+    /// Set the player's playback rate back to 1.0
+    PlaybackRateReset,
+    /// Move the OBS preview scene to program (studio mode)
+    ObsTransition,
+    /// Toggle the Home Assistant entity `ha_entity`
+    HaToggle,
+    /// Send `webhook_press_body` to `webhook_press_url`
+    Webhook,
+    /// Switch this action to the next rotation mode
+    CycleMode,
+    Custom,
+}
+
+/// How Zoom mode asks the focused application to zoom
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum ZoomStrategy {
+    /// Ctrl + mouse wheel
+    #[default]
+    Wheel,
+    /// Ctrl+= and Ctrl+-, for applications that ignore Ctrl+wheel
+    Keys,
+}
+
+/// Shortcuts TabSwitch mode sends
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum TabKeys {
+    /// Ctrl+Tab / Ctrl+Shift+Tab, closing with Ctrl+W
+    #[default]
+    #[serde(rename = "ctrl_tab")]
+    Tab,
+    /// Ctrl+PageDown / Ctrl+PageUp, closing with Ctrl+W
+    #[serde(rename = "ctrl_page")]
+    Page,
+    /// Ctrl+Shift+Right / Ctrl+Shift+Left, closing with Ctrl+Shift+W, as
+    /// terminal emulators that keep Ctrl+Tab for the shell use
+    #[serde(rename = "ctrl_shift_arrow")]
+    Arrow,
+}
+
+/// Shortcut UndoRedo mode sends for redo
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+enum RedoKeys {
+    #[default]
+    #[serde(rename = "ctrl_shift_z")]
+    ShiftZ,
+    /// Ctrl+Y, as Windows-style editors use
+    #[serde(rename = "ctrl_y")]
+    Y,
+}
+
+/// What Obs mode turns
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum ObsTarget {
+    /// Volume of `obs_input` in dB
+    #[default]
+    InputVolume,
+    /// Program scene, in scene list order
+    Scene,
+}
+
+/// Axis MouseMove mode moves the pointer along
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum MouseAxis {
+    /// Clockwise moves right
+    #[default]
+    #[serde(alias = "X")]
+    X,
+    /// Clockwise moves down
+    #[serde(alias = "Y")]
+    Y,
+}
+
+/// How far a Media Seek tick moves
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum SeekMode {
+    /// `seek_seconds` per tick
+    #[default]
+    Seconds,
+    /// `seek_percent` of the track length per tick
+    Percent,
+    /// To the next multiple of `seek_percent` of the track, so the dial
+    /// steps through fixed points from start to end
+    Absolute,
+}
+
+/// What Mpd mode turns
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum MpdTarget {
+    /// MPD's volume by `volume_step`
+    #[default]
+    Volume,
+    /// Position in the current song by `seek_seconds`
+    Seek,
+}
+
+/// Compositor Workspace mode talks to
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum WorkspaceBackend {
+    /// Whichever compositor's IPC variable is set
+    #[default]
+    Auto,
+    /// swaymsg
+    Sway,
+    /// i3-msg
+    I3,
+    /// hyprctl
+    Hyprland,
+}
+
+/// Compositor `WorkspaceBackend::Auto` stands for, from the environment the
+/// plugin started in. sway also sets I3SOCK, so it is checked first.
+static DETECTED_WORKSPACE_BACKEND: LazyLock<Option<WorkspaceBackend>> = LazyLock::new(|| {
+    let set = |var| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+    let backend = if set("SWAYSOCK") {
+        Some(WorkspaceBackend::Sway)
+    } else if set("HYPRLAND_INSTANCE_SIGNATURE") {
+        Some(WorkspaceBackend::Hyprland)
+    } else if set("I3SOCK") {
+        Some(WorkspaceBackend::I3)
+    } else {
+        None
+    };
+    log::info!("Auto workspace backend: {:?}", backend);
+    backend
+});
+
+impl WorkspaceBackend {
+    /// The compositor to drive, resolving Auto; None when none was detected
+    fn resolve(self) -> Option<WorkspaceBackend> {
+        match self {
+            WorkspaceBackend::Auto => *DETECTED_WORKSPACE_BACKEND,
+            backend => Some(backend),
+        }
+    }
+
+    /// The compositor's IPC client
+    fn tool(self) -> &'static str {
+        match self {
+            WorkspaceBackend::Auto | WorkspaceBackend::Sway => "swaymsg",
+            WorkspaceBackend::I3 => "i3-msg",
+            WorkspaceBackend::Hyprland => "hyprctl",
+        }
+    }
+}
+
+/// Tool used to inject wheel events in Scroll mode
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum ScrollBackend {
+    /// ydotool on Wayland sessions, otherwise xdotool
+    #[default]
+    Auto,
+    /// X11 only
+    Xdotool,
+    /// Works on Wayland via uinput, needs ydotoold running
+    Ydotool,
+    /// Built-in virtual device; needs the `uinput` cargo feature and access
+    /// to /dev/uinput, otherwise the Auto tool is used
+    Uinput,
+    /// SendInput, what Auto picks on Windows
+    SendInput,
+    /// Quartz scroll events, what Auto picks on macOS
+    Quartz,
+}
+
+/// Backend `ScrollBackend::Auto` stands for, chosen once at startup from
+/// the session type and the tools on PATH
+static DETECTED_SCROLL_BACKEND: LazyLock<ScrollBackend> = LazyLock::new(|| {
+    let capabilities = STATE.capabilities();
+    let wayland = capabilities.session_type == "wayland" || (capabilities.wayland && !capabilities.x11);
+    let (backend, reason) = if cfg!(windows) {
+        (ScrollBackend::SendInput, "Windows")
+    } else if cfg!(target_os = "macos") {
+        (ScrollBackend::Quartz, "macOS")
+    } else if wayland && capabilities.has("ydotool") {
+        (ScrollBackend::Ydotool, "Wayland session")
+    } else if capabilities.has("xdotool") {
+        (ScrollBackend::Xdotool, if wayland { "Wayland session but ydotool not found" } else { "X11 session" })
+    } else if capabilities.has("ydotool") {
+        (ScrollBackend::Ydotool, "xdotool not found")
+    } else {
+        (ScrollBackend::Xdotool, "neither xdotool nor ydotool found")
+    };
+    log::info!("Auto scroll backend: {:?} ({})", backend, reason);
+    backend
+});
+
+impl ScrollBackend {
+    /// The injector to use: Xdotool or Ydotool, SendInput on Windows or
+    /// Quartz on macOS.
+    /// Auto, and Uinput when the virtual device is unavailable, use the
+    /// detected one.
+    fn tool(&self) -> ScrollBackend {
+        match self {
+            ScrollBackend::Auto => DETECTED_SCROLL_BACKEND.clone(),
+            ScrollBackend::Uinput => {
+                #[cfg(not(all(feature = "uinput", target_os = "linux")))]
+                {
+                    static WARNED: std::sync::Once = std::sync::Once::new();
+                    WARNED.call_once(|| log::warn!("Built without the uinput feature; using the auto scroll backend"));
+                }
+                DETECTED_SCROLL_BACKEND.clone()
+            }
+            #[cfg(not(windows))]
+            ScrollBackend::SendInput => DETECTED_SCROLL_BACKEND.clone(),
+            #[cfg(not(target_os = "macos"))]
+            ScrollBackend::Quartz => DETECTED_SCROLL_BACKEND.clone(),
+            backend => backend.clone(),
+        }
     }
 }
 
 /// Settings for rotate action
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct RotateSettings {
+    /// Shape these settings were stored in; 0 is anything from before
+    /// versioning
     #[serde(default)]
-    mode: ActionMode,
+    version: u32,
+    /// Unset until the action first appears, when the encoder's default
+    /// mode is filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<ActionMode>,
+    /// Show the active mode as the dial's title, updated when the mode changes
     #[serde(default)]
-    cw_command: String,
+    show_mode_indicator: bool,
+    /// Briefly show the new level on the dial after a Volume, Mic Volume,
+    /// Brightness or MonitorBrightness rotation
+    #[serde(default = "default_show_level")]
+    show_level: bool,
+    /// Custom mode command template for both directions; overrides
+    /// cw_command/ccw_command when set
     #[serde(default)]
+    command: String,
+    /// Run custom commands through `sh -c`; false splits them into argv
+    /// with shell quoting rules and runs the program directly
+    #[serde(default = "default_use_shell")]
+    use_shell: bool,
+    #[serde(default, alias = "cwCommand")]
+    cw_command: String,
+    #[serde(default, alias = "ccwCommand")]
     ccw_command: String,
+    /// Key specs for Keys mode, see `keyspec`; also override the arrows
+    /// in Pan mode
+    #[serde(default, alias = "cw_key")]
+    cw_keys: String,
+    #[serde(default, alias = "ccw_key")]
+    ccw_keys: String,
+    /// Key spec for the `keys` press action
+    #[serde(default)]
+    press_keys: String,
+    /// Volume change per tick in percent
+    #[serde(default = "default_volume_step")]
+    volume_step: i32,
+    #[serde(default, alias = "audio_backend")]
+    volume_backend: VolumeBackend,
+    /// Mixer element changed by the amixer and alsa backends
+    #[serde(default = "default_mixer_element")]
+    mixer_element: String,
+    /// Microphone volume change per tick in percent, for MicVolume mode
+    #[serde(default = "default_volume_step")]
+    mic_volume_step: i32,
+    /// Capture element changed by the amixer and alsa backends in MicVolume mode
+    #[serde(default = "default_mic_element")]
+    mic_element: String,
+    /// Regex; sinks whose name or description matches are skipped in
+    /// SinkSwitch mode
+    #[serde(default)]
+    sink_exclude: Option<String>,
+    /// Sink name or description the FavoriteSink press switches to
+    #[serde(default)]
+    favorite_sink: Option<String>,
+    /// playerctl player name to control; None lets playerctl choose
+    #[serde(default)]
+    player: Option<String>,
+    /// Seconds to seek per tick in MediaSeek mode, and the fallback of the
+    /// other seek modes when the track length is unknown
+    #[serde(default = "default_seek_seconds")]
+    seek_seconds: u32,
+    #[serde(default)]
+    seek_mode: SeekMode,
+    /// Share of the track per tick in percent, for the percent and absolute
+    /// seek modes
+    #[serde(default = "default_seek_percent")]
+    seek_percent: f32,
+    /// Wheel clicks per tick in Scroll mode; 0 disables scrolling
+    #[serde(default = "default_scroll_lines")]
+    scroll_lines: u32,
+    /// Wheel clicks per tick in HScroll mode; 0 disables scrolling
+    #[serde(default = "default_scroll_lines")]
+    hscroll_columns: u32,
+    /// Scroll up on clockwise (natural scrolling)
+    #[serde(default)]
+    invert_scroll: bool,
+    #[serde(default)]
+    scroll_backend: ScrollBackend,
+    #[serde(default)]
+    zoom_strategy: ZoomStrategy,
+    #[serde(default)]
+    workspace_backend: WorkspaceBackend,
+    /// Go from the last workspace to the first (and back) instead of
+    /// stopping at the ends
+    #[serde(default = "default_workspace_wrap")]
+    workspace_wrap: bool,
+    #[serde(default)]
+    tab_keys: TabKeys,
+    #[serde(default)]
+    redo_keys: RedoKeys,
+    /// Release Alt after the dial has rested this long in WindowSwitch mode
+    #[serde(default = "default_window_switch_timeout_ms")]
+    window_switch_timeout_ms: u64,
+    /// Playback rate change per tick in PlaybackRate mode
+    #[serde(default = "default_playback_rate_step")]
+    playback_rate_step: f32,
+    /// Axis and pixels per tick for MouseMove mode
+    #[serde(default)]
+    mouse_axis: MouseAxis,
+    #[serde(default = "default_mouse_step")]
+    mouse_step: u32,
+    /// Brightness change per tick in percent
+    #[serde(default = "default_brightness_step")]
+    brightness_step: u32,
+    /// brightnessctl device (e.g. intel_backlight); None uses its default
+    #[serde(default)]
+    brightness_device: Option<String>,
+    /// Monitor for MonitorBrightness: a ddcutil display number or a serial
+    /// number; None uses the first one ddcutil finds
+    #[serde(default)]
+    display: Option<String>,
+    /// Color temperature change per tick in kelvin
+    #[serde(default = "default_color_temp_step")]
+    color_temp_step: u32,
+    /// obs-websocket server for Obs mode
+    #[serde(default = "default_obs_host")]
+    obs_host: String,
+    #[serde(default = "default_obs_port")]
+    obs_port: u16,
+    #[serde(default)]
+    obs_password: String,
+    #[serde(default)]
+    obs_target: ObsTarget,
+    /// OBS input whose volume Obs mode turns, e.g. "Mic/Aux"
+    #[serde(default)]
+    obs_input: String,
+    /// Input volume change per tick in dB
+    #[serde(default = "default_obs_volume_step")]
+    obs_volume_step: f32,
+    /// MPD server for Mpd mode
+    #[serde(default = "default_mpd_host")]
+    mpd_host: String,
+    #[serde(default = "default_mpd_port")]
+    mpd_port: u16,
+    #[serde(default)]
+    mpd_target: MpdTarget,
+    /// Home Assistant base URL for HomeAssistant mode, e.g.
+    /// "http://homeassistant.local:8123"
+    #[serde(default)]
+    ha_url: String,
+    /// Long-lived access token
+    #[serde(default)]
+    ha_token: String,
+    /// Light to dim, e.g. "light.desk"
+    #[serde(default)]
+    ha_entity: String,
+    /// Accept self-signed certificates
+    #[serde(default)]
+    ha_insecure: bool,
+    /// HTTP method, URL and "Name: value" header lines for Webhook mode;
+    /// the URL and body may use the Custom mode placeholders
+    #[serde(default = "default_webhook_method")]
+    webhook_method: String,
+    #[serde(default)]
+    webhook_url: String,
+    #[serde(default)]
+    webhook_headers: String,
+    #[serde(default)]
+    webhook_body: String,
+    /// Request the webhook press action sends, with the same method and
+    /// headers; the body is sent as written
+    #[serde(default)]
+    webhook_press_url: String,
+    #[serde(default)]
+    webhook_press_body: String,
+    /// Physical ticks needed for one action; direction changes reset the count
+    #[serde(default = "default_ticks_per_action", alias = "detents_per_action")]
+    ticks_per_action: u32,
+    /// Kill commands still running after this long; None uses the plugin default
+    #[serde(default)]
+    command_timeout_ms: Option<u64>,
+    /// Minimum time between two commands of this mode; rotations in between
+    /// are added up and run together. 0 is unlimited.
+    #[serde(default)]
+    rate_limit_ms: u64,
+    /// Collect ticks for this long and run one command for their sum; 0 runs every tick
+    #[serde(default = "default_coalesce_ms")]
+    coalesce_ms: u64,
+    /// Multiply the step when the dial is spun quickly (never for MediaTrack)
+    #[serde(default)]
+    acceleration: bool,
+    /// Tick rate (ticks/s) at or below which the step is not multiplied
+    #[serde(default = "default_acceleration_min_rate")]
+    acceleration_min_rate: f32,
+    /// Tick rate (ticks/s) at or above which the full factor applies
+    #[serde(default = "default_acceleration_max_rate")]
+    acceleration_max_rate: f32,
+    /// Largest step multiplier, reached at `acceleration_max_rate`
+    #[serde(default = "default_acceleration_factor")]
+    acceleration_factor: u32,
+    /// Swap clockwise and counter-clockwise for every mode
+    #[serde(default, alias = "invert_dial")]
+    reverse: bool,
+    /// Ignore a press arriving this soon after the previous release (contact bounce)
+    #[serde(default = "default_press_debounce_ms")]
+    press_debounce_ms: u64,
+    /// Run once per short press/release cycle, on release
+    #[serde(default, alias = "short_press_action", alias = "press_mode")]
+    press_action: DialPressAction,
+    /// Shell command for the Custom press action
+    #[serde(default)]
+    press_command: String,
+    /// Hold the dial this long to run the long press action instead of the press action
+    #[serde(default = "default_long_press_ms")]
+    long_press_ms: u64,
+    #[serde(default)]
+    long_press_action: DialPressAction,
+    /// Shell command for the Custom long press action
+    #[serde(default)]
+    long_press_command: String,
+    /// Two presses within `double_press_ms` run this instead of the press action twice
+    #[serde(default)]
+    double_press_action: DialPressAction,
+    #[serde(default)]
+    double_press_command: String,
+    #[serde(default = "default_double_press_ms")]
+    double_press_ms: u64,
+    /// Mode used while the dial is held down; None keeps the normal mode
+    #[serde(default, alias = "held_mode")]
+    pressed_mode: Option<ActionMode>,
+    #[serde(default, alias = "held_cw_command")]
+    pressed_cw_command: String,
+    #[serde(default, alias = "held_ccw_command")]
+    pressed_ccw_command: String,
+}
+
+fn default_show_level() -> bool {
+    true
+}
+
+fn default_use_shell() -> bool {
+    true
+}
+
+fn default_volume_step() -> i32 {
+    5
+}
+
+fn default_mixer_element() -> String {
+    "Master".to_string()
+}
+
+fn default_mic_element() -> String {
+    "Capture".to_string()
+}
+
+fn default_seek_seconds() -> u32 {
+    5
+}
+
+fn default_seek_percent() -> f32 {
+    1.0
+}
+
+fn default_scroll_lines() -> u32 {
+    3
+}
+
+fn default_workspace_wrap() -> bool {
+    true
+}
+
+fn default_window_switch_timeout_ms() -> u64 {
+    800
+}
+
+fn default_brightness_step() -> u32 {
+    10
+}
+
+fn default_mouse_step() -> u32 {
+    10
+}
+
+fn default_playback_rate_step() -> f32 {
+    0.1
+}
+
+fn default_obs_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_obs_port() -> u16 {
+    4455
+}
+
+fn default_obs_volume_step() -> f32 {
+    1.0
+}
+
+fn default_mpd_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_mpd_port() -> u16 {
+    6600
+}
+
+fn default_webhook_method() -> String {
+    "POST".to_string()
+}
+
+fn default_color_temp_step() -> u32 {
+    250
+}
+
+fn default_ticks_per_action() -> u32 {
+    1
+}
+
+fn default_coalesce_ms() -> u64 {
+    40
+}
+
+fn default_acceleration_min_rate() -> f32 {
+    5.0
+}
+
+fn default_acceleration_max_rate() -> f32 {
+    15.0
+}
+
+fn default_acceleration_factor() -> u32 {
+    3
+}
+
+fn default_press_debounce_ms() -> u64 {
+    30
+}
+
+fn default_long_press_ms() -> u64 {
+    600
+}
+
+fn default_double_press_ms() -> u64 {
+    350
 }
 
 impl Default for RotateSettings {
     fn default() -> Self {
         Self {
-            mode: ActionMode::Volume,
+            version: SETTINGS_VERSION,
+            mode: None,
+            show_mode_indicator: false,
+            show_level: default_show_level(),
+            command: String::new(),
+            use_shell: default_use_shell(),
             cw_command: String::new(),
             ccw_command: String::new(),
+            cw_keys: String::new(),
+            ccw_keys: String::new(),
+            press_keys: String::new(),
+            volume_step: default_volume_step(),
+            volume_backend: VolumeBackend::Auto,
+            mixer_element: default_mixer_element(),
+            mic_volume_step: default_volume_step(),
+            mic_element: default_mic_element(),
+            sink_exclude: None,
+            favorite_sink: None,
+            player: None,
+            seek_seconds: default_seek_seconds(),
+            seek_mode: SeekMode::default(),
+            seek_percent: default_seek_percent(),
+            scroll_lines: default_scroll_lines(),
+            hscroll_columns: default_scroll_lines(),
+            invert_scroll: false,
+            scroll_backend: ScrollBackend::Auto,
+            zoom_strategy: ZoomStrategy::Wheel,
+            workspace_backend: WorkspaceBackend::Auto,
+            workspace_wrap: default_workspace_wrap(),
+            tab_keys: TabKeys::Tab,
+            redo_keys: RedoKeys::ShiftZ,
+            window_switch_timeout_ms: default_window_switch_timeout_ms(),
+            brightness_step: default_brightness_step(),
+            brightness_device: None,
+            display: None,
+            color_temp_step: default_color_temp_step(),
+            playback_rate_step: default_playback_rate_step(),
+            mouse_axis: MouseAxis::default(),
+            mouse_step: default_mouse_step(),
+            obs_host: default_obs_host(),
+            obs_port: default_obs_port(),
+            obs_password: String::new(),
+            obs_target: ObsTarget::default(),
+            obs_input: String::new(),
+            obs_volume_step: default_obs_volume_step(),
+            mpd_host: default_mpd_host(),
+            mpd_port: default_mpd_port(),
+            mpd_target: MpdTarget::default(),
+            ha_url: String::new(),
+            ha_token: String::new(),
+            ha_entity: String::new(),
+            ha_insecure: false,
+            webhook_method: default_webhook_method(),
+            webhook_url: String::new(),
+            webhook_headers: String::new(),
+            webhook_body: String::new(),
+            webhook_press_url: String::new(),
+            webhook_press_body: String::new(),
+            ticks_per_action: default_ticks_per_action(),
+            command_timeout_ms: None,
+            rate_limit_ms: 0,
+            coalesce_ms: default_coalesce_ms(),
+            acceleration: false,
+            acceleration_min_rate: default_acceleration_min_rate(),
+            acceleration_max_rate: default_acceleration_max_rate(),
+            acceleration_factor: default_acceleration_factor(),
+            reverse: false,
+            press_debounce_ms: default_press_debounce_ms(),
+            press_action: DialPressAction::None,
+            press_command: String::new(),
+            long_press_ms: default_long_press_ms(),
+            long_press_action: DialPressAction::None,
+            long_press_command: String::new(),
+            double_press_action: DialPressAction::None,
+            double_press_command: String::new(),
+            double_press_ms: default_double_press_ms(),
+            pressed_mode: None,
+            pressed_cw_command: String::new(),
+            pressed_ccw_command: String::new(),
         }
     }
 }
 
-/// Settings for press action (no special settings needed)
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
-struct PressSettings {}
+/// Where dial_rotate sends the signed steps of a rotation
+#[derive(Debug, PartialEq)]
+enum Dispatch {
+    /// To the executor straight away
+    Submit(i64),
+    /// Into the dial's coalesce window, which runs the net steps when it
+    /// closes
+    Coalesce(i64, Duration),
+}
+
+impl RotateSettings {
+    /// These settings in the current shape, or None if they already are.
+    /// Storing the result also rewrites keys read through serde aliases
+    /// (cwCommand, audio_backend, press_mode, ...) under their current
+    /// names, which the inspector knows; left alone, the inspector would
+    /// save the current name next to the old one and serde would reject
+    /// the duplicate.
+    fn migrate(&self) -> Option<RotateSettings> {
+        if self.version >= SETTINGS_VERSION {
+            return None;
+        }
+        let mut migrated = RotateSettings { version: SETTINGS_VERSION, ..self.clone() };
+        // 0 -> 1: a bare long_press_command predates long_press_action and
+        // ran as Custom
+        if migrated.long_press_action == DialPressAction::None && !migrated.long_press_command.is_empty() {
+            migrated.long_press_action = DialPressAction::Custom;
+        }
+        // Steps were not validated; keep the ones they ran with
+        migrated.volume_step = self.volume_step() as i32;
+        migrated.mic_volume_step = self.mic_volume_step() as i32;
+        Some(migrated)
+    }
+
+    /// The rotation mode; Volume if none has been set yet
+    fn mode(&self) -> ActionMode {
+        self.mode.clone().unwrap_or_default()
+    }
+
+    /// Player the media modes control: `player` if set, otherwise the one
+    /// picked in PlayerSelect mode, otherwise None for the active one
+    fn player(&self) -> Option<String> {
+        self.player.clone().filter(|p| !p.is_empty()).or_else(|| STATE.selected_player())
+    }
+
+    /// Direction of a rotation (+1 CW, -1 CCW) after applying `reverse`.
+    /// This is the only place the direction is inverted.
+    fn direction(&self, ticks: i16) -> i8 {
+        let direction = if ticks > 0 { 1 } else { -1 };
+        if self.reverse { -direction } else { direction }
+    }
+
+    /// Settings to rotate with while the dial is held, if a pressed mode is set
+    fn pressed_settings(&self) -> Option<RotateSettings> {
+        let mode = self.pressed_mode.clone()?;
+        Some(RotateSettings {
+            mode: Some(mode),
+            command: String::new(),
+            cw_command: self.pressed_cw_command.clone(),
+            ccw_command: self.pressed_ccw_command.clone(),
+            ..self.clone()
+        })
+    }
+
+    /// How a rotation of `magnitude` steps in `direction` goes out, once
+    /// ticks_per_action and acceleration have been applied
+    fn dispatch(&self, direction: i8, magnitude: u32) -> Dispatch {
+        let steps = i64::from(direction) * i64::from(magnitude);
+        if self.coalesce_ms == 0 || !self.mode().coalesces() {
+            return Dispatch::Submit(steps);
+        }
+        Dispatch::Coalesce(steps, Duration::from_millis(self.coalesce_ms))
+    }
+
+    /// Step multiplier for a tick arriving `interval` after the previous one.
+    /// Scales linearly from 1x at `acceleration_min_rate` to
+    /// `acceleration_factor` (at most 10, as in the inspector) at
+    /// `acceleration_max_rate`; slower ticks (and the first tick after a
+    /// pause) always get 1x.
+    fn acceleration_multiplier(&self, interval: Duration) -> u32 {
+        let factor = self.acceleration_factor.clamp(1, 10);
+        if !self.acceleration || !self.mode().supports_acceleration() || factor == 1 {
+            return 1;
+        }
+        let rate = 1.0 / interval.as_secs_f32().max(0.001);
+        let (min, max) = (self.acceleration_min_rate, self.acceleration_max_rate);
+        if rate <= min {
+            return 1;
+        }
+        if rate >= max || max <= min {
+            return factor;
+        }
+        let t = (rate - min) / (max - min);
+        1 + ((factor - 1) as f32 * t).round() as u32
+    }
+
+    /// The obs-websocket server Obs mode talks to
+    #[cfg(feature = "obs")]
+    fn obs_server(&self) -> obs::Server {
+        let host = if self.obs_host.trim().is_empty() { default_obs_host() } else { self.obs_host.trim().to_string() };
+        obs::Server { host, port: self.obs_port, password: self.obs_password.clone() }
+    }
+
+    /// The MPD server Mpd mode talks to
+    fn mpd_server(&self) -> mpd::Server {
+        let host = if self.mpd_host.trim().is_empty() { default_mpd_host() } else { self.mpd_host.trim().to_string() };
+        mpd::Server { host, port: self.mpd_port }
+    }
+
+    /// The Home Assistant server HomeAssistant mode talks to
+    #[cfg(feature = "home-assistant")]
+    fn ha_server(&self) -> home_assistant::Server {
+        home_assistant::Server {
+            url: self.ha_url.trim().trim_end_matches('/').to_string(),
+            token: self.ha_token.trim().to_string(),
+            insecure: self.ha_insecure,
+        }
+    }
+
+    /// How long a command may run before it is killed
+    fn command_timeout(&self) -> Duration {
+        let ms = self.command_timeout_ms.unwrap_or(*DEFAULT_COMMAND_TIMEOUT_MS);
+        Duration::from_millis(ms.max(1))
+    }
+
+    /// Volume step in percent, clamped to 1..=25
+    fn volume_step(&self) -> u32 {
+        if self.volume_step <= 0 {
+            log::warn!("Invalid volume_step {}, using {}", self.volume_step, default_volume_step());
+            return default_volume_step() as u32;
+        }
+        self.volume_step.min(25) as u32
+    }
+
+    /// Mode whose mute a `mute_toggle` press flips: in MicVolume mode the
+    /// dial mutes what it turns, otherwise the output
+    fn mute_target(&self) -> ActionMode {
+        if self.mode() == ActionMode::MicVolume { ActionMode::MicVolume } else { ActionMode::Volume }
+    }
+
+    /// Mode whose play/pause a `play_pause` press runs: MPD in Mpd mode,
+    /// otherwise the media player
+    fn play_pause_target(&self) -> ActionMode {
+        if self.mode() == ActionMode::Mpd { ActionMode::Mpd } else { ActionMode::MediaTrack }
+    }
+
+    /// Whether any press on this dial toggles mute
+    fn toggles_mute(&self) -> bool {
+        [&self.press_action, &self.long_press_action, &self.double_press_action].contains(&&DialPressAction::MuteToggle)
+    }
+
+    /// Microphone volume step in percent, clamped to 1..=25
+    fn mic_volume_step(&self) -> u32 {
+        if self.mic_volume_step <= 0 {
+            log::warn!("Invalid mic_volume_step {}, using {}", self.mic_volume_step, default_volume_step());
+            return default_volume_step() as u32;
+        }
+        self.mic_volume_step.min(25) as u32
+    }
+}
+
+/// Settings for press action. It shares the rotate settings so the press
+/// action, command and command options work the same; the rotate-only
+/// fields are ignored.
+type PressSettings = RotateSettings;
 
 // ============================================================================
 // Global Handler
@@ -69,6 +1128,34 @@ struct N1EncoderGlobalHandler;
 impl GlobalEventHandler for N1EncoderGlobalHandler {
     async fn plugin_ready(&self) -> OpenActionResult<()> {
         log::info!("N1 Encoder Actions plugin initialized");
+        LazyLock::force(&DETECTED_VOLUME_BACKEND);
+        LazyLock::force(&DETECTED_SCROLL_BACKEND);
+        #[cfg(unix)]
+        status::spawn();
+        // The reply restores state kept across restarts, e.g. the color temperature
+        if let Err(e) = openaction::get_global_settings().await {
+            log::warn!("Failed to request global settings: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn did_receive_global_settings(&self, event: DidReceiveGlobalSettingsEvent) -> OpenActionResult<()> {
+        if let Some(temp) = event.payload.settings.get("color_temp").and_then(|t| t.as_u64()) {
+            modes::restore_color_temp(u32::try_from(temp).unwrap_or(u32::MAX)).await;
+        }
+        Ok(())
+    }
+
+    async fn device_did_connect(&self, event: DeviceDidConnectEvent) -> OpenActionResult<()> {
+        log::info!("Device connected: {} ({})", event.device, event.deviceInfo.name);
+        STATE.device_connected(&event.device, &event.deviceInfo.name);
+        Ok(())
+    }
+
+    async fn device_did_disconnect(&self, event: DeviceDidDisconnectEvent) -> OpenActionResult<()> {
+        log::info!("Device disconnected: {}", event.device);
+        STATE.device_disconnected(&event.device);
+        modes::release_alt().await;
         Ok(())
     }
 }
@@ -89,7 +1176,24 @@ impl Action for RotateAction {
         instance: &Instance,
         settings: &Self::Settings,
     ) -> OpenActionResult<()> {
-        log::info!("Rotate action appeared: {} (mode: {:?})", instance.instance_id, settings.mode);
+        let mut updated = settings.migrate();
+        if settings.mode.is_none() {
+            let encoder = instance.coordinates.as_ref().map(|c| c.column);
+            let base = updated.take().unwrap_or_else(|| settings.clone());
+            updated = Some(RotateSettings { mode: Some(STATE.default_mode(encoder)), ..base });
+        }
+        let settings = updated.as_ref().unwrap_or(settings);
+        log::info!("Rotate action appeared: {} (mode: {:?})", instance.instance_id, settings.mode());
+        STATE.track(&instance.instance_id, &instance.device_id, Some(settings.mode()));
+        if updated.is_some() {
+            // Store the mode and current shape so the inspector and later
+            // events see them
+            instance.set_settings(settings).await?;
+        }
+        if settings.show_mode_indicator {
+            show_mode_indicator(instance, settings).await;
+        }
+        sync_mute_state(instance, settings);
         Ok(())
     }
 
@@ -99,6 +1203,7 @@ impl Action for RotateAction {
         _settings: &Self::Settings,
     ) -> OpenActionResult<()> {
         log::info!("Rotate action disappeared: {}", instance.instance_id);
+        STATE.remove(&instance.instance_id);
         Ok(())
     }
 
@@ -112,19 +1217,121 @@ impl Action for RotateAction {
         ticks: i16,
         pressed: bool,
     ) -> OpenActionResult<()> {
-        let direction = if ticks > 0 { 1 } else { -1 };
-        log::info!(
-            "Dial rotate: {} (ticks: {}, pressed: {}, mode: {:?})",
-            instance.instance_id, ticks, pressed, settings.mode
+        STATE.record_input(&instance.instance_id);
+        let held = STATE.rotate_while_held(&instance.instance_id, pressed);
+        let pressed_settings = if held { settings.pressed_settings() } else { None };
+        let settings = pressed_settings.as_ref().unwrap_or(settings);
+
+        let direction = settings.direction(ticks);
+        let steps = STATE.divide_ticks(&instance.instance_id, direction, u32::from(ticks.unsigned_abs()), settings);
+        if steps == 0 {
+            log::debug!("Dial rotate: {} (ticks: {}, below ticks_per_action)", instance.instance_id, ticks);
+            return Ok(());
+        }
+        let magnitude = STATE.rotation_magnitude(&instance.instance_id, steps, settings);
+        let level = if STATE.log_allowed("Dial rotate", &settings.mode()) { log::Level::Info } else { log::Level::Debug };
+        log::log!(
+            level,
+            "Dial rotate: {} (ticks: {}, magnitude: {}, pressed: {}, mode: {:?})",
+            instance.instance_id, ticks, magnitude, held, settings.mode()
         );
 
-        if let Err(e) = execute_rotation(direction, settings) {
-            log::error!("Rotation action failed: {}", e);
-            let _ = instance.show_alert().await;
-        } else {
-            let _ = instance.show_ok().await;
+        let (steps, window) = match settings.dispatch(direction, magnitude) {
+            Dispatch::Submit(steps) => {
+                executor::submit(instance.instance_id.clone(), CommandContext::new(instance, held), steps, settings.clone());
+                return Ok(());
+            }
+            Dispatch::Coalesce(steps, window) => (steps, window),
+        };
+
+        // Collect the burst and run its net movement once the window closes
+        let instance_id = instance.instance_id.clone();
+        let context = CommandContext::new(instance, held);
+        let settings = settings.clone();
+        STATE.coalesce(&instance.instance_id, steps, window, move |steps| {
+            log::debug!("Coalesced rotation: {} ({} steps)", instance_id, steps);
+            executor::submit(instance_id, context, steps, settings);
+        });
+
+        Ok(())
+    }
+
+    /// Called when the dial is pressed down
+    async fn dial_down(
+        &self,
+        instance: &Instance,
+        settings: &Self::Settings,
+    ) -> OpenActionResult<()> {
+        STATE.record_input(&instance.instance_id);
+        if !STATE.press_down(&instance.instance_id, settings) {
+            log::debug!("Dial press debounced: {}", instance.instance_id);
+            return Ok(());
+        }
+        log::info!("Dial pressed: {}", instance.instance_id);
+
+        if settings.mode() == ActionMode::WindowSwitch && modes::release_alt().await {
+            // The press picks the highlighted window and nothing else
+            STATE.consume_press(&instance.instance_id);
+            return Ok(());
+        }
+
+        let long_press_action = settings.long_press_action.clone();
+        if long_press_action != DialPressAction::None {
+            let instance_id = instance.instance_id.clone();
+            let settings = settings.clone();
+            let timer = tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(settings.long_press_ms)).await;
+                if !STATE.fire_long_press(&instance_id) {
+                    return;
+                }
+                log::info!("Dial long press: {} ({:?})", instance_id, long_press_action);
+                if let Some(instance) = openaction::get_instance(instance_id).await {
+                    run_press_action(&instance, &long_press_action, &settings.long_press_command, &settings).await;
+                }
+            });
+            STATE.start_long_press(&instance.instance_id, timer);
         }
+        Ok(())
+    }
+
+    /// Called when the dial is released
+    async fn dial_up(
+        &self,
+        instance: &Instance,
+        settings: &Self::Settings,
+    ) -> OpenActionResult<()> {
+        if !STATE.press_up(&instance.instance_id) {
+            return Ok(());
+        }
+        log::info!("Dial released: {} (press action: {:?})", instance.instance_id, settings.press_action);
 
+        if settings.double_press_action == DialPressAction::None {
+            spawn_press_action(
+                instance.instance_id.clone(),
+                settings.press_action.clone(),
+                settings.press_command.clone(),
+                settings.clone(),
+            );
+            return Ok(());
+        }
+
+        // Hold the single press back until we know no second press follows
+        let instance_id = instance.instance_id.clone();
+        let held = settings.clone();
+        let single = async move {
+            if let Some(instance) = openaction::get_instance(instance_id).await {
+                run_press_action(&instance, &held.press_action, &held.press_command, &held).await;
+            }
+        };
+        if STATE.defer_press(&instance.instance_id, Duration::from_millis(settings.double_press_ms), single) {
+            log::info!("Dial double press: {} ({:?})", instance.instance_id, settings.double_press_action);
+            spawn_press_action(
+                instance.instance_id.clone(),
+                settings.double_press_action.clone(),
+                settings.double_press_command.clone(),
+                settings.clone(),
+            );
+        }
         Ok(())
     }
 
@@ -133,9 +1340,153 @@ impl Action for RotateAction {
         instance: &Instance,
         settings: &Self::Settings,
     ) -> OpenActionResult<()> {
-        log::info!("Rotate settings updated: {} (mode: {:?})", instance.instance_id, settings.mode);
+        log::info!("Rotate settings updated: {} (mode: {:?})", instance.instance_id, settings.mode());
+        store_migrated(instance, settings).await?;
+        STATE.track(&instance.instance_id, &instance.device_id, Some(settings.mode()));
+        show_mode_indicator(instance, settings).await;
+        sync_mute_state(instance, settings);
+        #[cfg(feature = "obs")]
+        push_obs_names(instance, settings);
         Ok(())
     }
+
+    async fn property_inspector_did_appear(
+        &self,
+        instance: &Instance,
+        settings: &Self::Settings,
+    ) -> OpenActionResult<()> {
+        // Tell the inspector which modes cannot work here so it can grey them out
+        let mut unavailable = serde_json::Map::new();
+        let mut mode = ActionMode::Volume;
+        loop {
+            if let Some(reason) = modes::executor(&mode).unavailable(settings) {
+                unavailable.insert(mode.as_str().to_string(), reason.into());
+            }
+            mode = mode.next();
+            if mode == ActionMode::Volume {
+                break;
+            }
+        }
+        #[cfg(feature = "obs")]
+        push_obs_names(instance, settings);
+        instance
+            .send_to_property_inspector(serde_json::json!({
+                "event": "capabilities",
+                "capabilities": STATE.capabilities(),
+                "unavailable": unavailable,
+            }))
+            .await
+    }
+
+    async fn send_to_plugin(
+        &self,
+        instance: &Instance,
+        settings: &Self::Settings,
+        payload: &serde_json::Value,
+    ) -> OpenActionResult<()> {
+        handle_inspector_message(instance, settings, payload).await
+    }
+}
+
+/// Store `settings` in the current shape if they were stored by an older
+/// version; the values in effect do not change
+async fn store_migrated(instance: &Instance, settings: &RotateSettings) -> OpenActionResult<()> {
+    if let Some(migrated) = settings.migrate() {
+        log::info!("Migrating settings of {} from version {} to {}", instance.instance_id, settings.version, SETTINGS_VERSION);
+        instance.set_settings(&migrated).await?;
+    }
+    Ok(())
+}
+
+/// Requests from the inspector: the "Send test request" buttons of Webhook
+/// mode send one clockwise tick or a press and report how it went
+async fn handle_inspector_message(instance: &Instance, settings: &RotateSettings, payload: &serde_json::Value) -> OpenActionResult<()> {
+    if payload["event"] != "webhook_test" {
+        return Ok(());
+    }
+    let executor = modes::executor(&ActionMode::Webhook);
+    let context = CommandContext::new(instance, false);
+    let result = if payload["target"] == "press" {
+        executor.press(&context, settings).await
+    } else {
+        executor.rotate(1, &context, settings).await
+    };
+    let message = match &result {
+        Ok(()) => "Sent; the endpoint answered 2xx".to_string(),
+        Err(e) => e.to_string(),
+    };
+    log::info!("Webhook test from the inspector: {}", message);
+    instance
+        .send_to_property_inspector(serde_json::json!({
+            "event": "webhook_result",
+            "ok": result.is_ok(),
+            "message": message,
+        }))
+        .await
+}
+
+/// Title the dial with its mode, or hand the title back to OpenDeck when the
+/// indicator is off
+async fn show_mode_indicator(instance: &Instance, settings: &RotateSettings) {
+    let title = settings.show_mode_indicator.then(|| settings.mode().title());
+    if let Err(e) = instance.set_title(title, None).await {
+        log::warn!("Failed to set mode indicator on {}: {}", instance.instance_id, e);
+    }
+}
+
+/// Send the OBS input and scene names to the inspector for its pickers, in
+/// the background. Nothing is sent when OBS cannot be reached; the dial
+/// alerts once it is used.
+#[cfg(feature = "obs")]
+fn push_obs_names(instance: &Instance, settings: &RotateSettings) {
+    if settings.mode() != ActionMode::Obs {
+        return;
+    }
+    let instance_id = instance.instance_id.clone();
+    let server = settings.obs_server();
+    tokio::spawn(async move {
+        let names = async { Ok::<_, error::ActionError>((obs::inputs(&server).await?, obs::scenes(&server).await?.1)) };
+        let (inputs, scenes) = match names.await {
+            Ok(names) => names,
+            Err(e) => {
+                log::debug!("No OBS names for the inspector: {}", e);
+                return;
+            }
+        };
+        let Some(instance) = openaction::get_instance(instance_id).await else {
+            return;
+        };
+        let message = serde_json::json!({ "event": "obs_names", "inputs": inputs, "scenes": scenes });
+        if let Err(e) = instance.send_to_property_inspector(message).await {
+            log::debug!("Failed to send OBS names to the inspector: {}", e);
+        }
+    });
+}
+
+/// Follow the mute state this dial toggles and show it as the instance
+/// state (1 muted, 0 unmuted), read from the backend in the background.
+/// Dials that do not toggle mute go back to state 0.
+fn sync_mute_state(instance: &Instance, settings: &RotateSettings) {
+    let mode = settings.toggles_mute().then(|| settings.mute_target());
+    STATE.watch_mute(&instance.instance_id, mode.clone());
+    let instance_id = instance.instance_id.clone();
+    let context = CommandContext::new(instance, false);
+    let settings = settings.clone();
+    tokio::spawn(async move {
+        let Some(instance) = openaction::get_instance(instance_id).await else {
+            return;
+        };
+        let Some(mode) = mode else {
+            if instance.current_state_index.load(Ordering::Relaxed) != 0 {
+                let _ = instance.set_state(0).await;
+            }
+            return;
+        };
+        if let Some(muted) = modes::executor(&mode).muted(&context, &settings).await {
+            commands::sync_mute(&mode, muted).await;
+            let _ = instance.set_state(u16::from(muted)).await;
+        }
+    });
 }
 
 // ============================================================================
@@ -152,9 +1503,12 @@ impl Action for PressAction {
     async fn will_appear(
         &self,
         instance: &Instance,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
     ) -> OpenActionResult<()> {
         log::info!("Press action appeared: {}", instance.instance_id);
+        store_migrated(instance, settings).await?;
+        STATE.track(&instance.instance_id, &instance.device_id, None);
+        sync_mute_state(instance, settings);
         Ok(())
     }
 
@@ -164,17 +1518,41 @@ impl Action for PressAction {
         _settings: &Self::Settings,
     ) -> OpenActionResult<()> {
         log::info!("Press action disappeared: {}", instance.instance_id);
+        STATE.remove(&instance.instance_id);
+        Ok(())
+    }
+
+    async fn did_receive_settings(
+        &self,
+        instance: &Instance,
+        settings: &Self::Settings,
+    ) -> OpenActionResult<()> {
+        store_migrated(instance, settings).await?;
+        sync_mute_state(instance, settings);
         Ok(())
     }
 
-    /// Called when encoder is pressed down
+    /// Called when encoder is pressed down. Runs the configured press action,
+    /// if any; multi-actions containing this action carry on as before.
     async fn dial_down(
         &self,
         instance: &Instance,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
     ) -> OpenActionResult<()> {
-        log::info!("Dial pressed: {}", instance.instance_id);
-        // The press action just triggers - the actual behavior is defined by multi-actions
+        log::info!("Dial pressed: {} (press action: {:?})", instance.instance_id, settings.press_action);
+        STATE.record_input(&instance.instance_id);
+        match settings.press_action {
+            DialPressAction::None => {}
+            DialPressAction::CycleMode => {
+                log::warn!("cycle_mode only applies to the rotate action: {}", instance.instance_id);
+            }
+            _ => spawn_press_action(
+                instance.instance_id.clone(),
+                settings.press_action.clone(),
+                settings.press_command.clone(),
+                settings.clone(),
+            ),
+        }
         Ok(())
     }
 
@@ -187,93 +1565,15 @@ impl Action for PressAction {
         log::info!("Dial released: {}", instance.instance_id);
         Ok(())
     }
-}
-
-// ============================================================================
-// Command Execution
-// ============================================================================
-
-fn execute_rotation(direction: i8, settings: &RotateSettings) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    match settings.mode {
-        ActionMode::Volume => execute_volume(direction),
-        ActionMode::MediaTrack => execute_media_track(direction),
-        ActionMode::MediaSeek => execute_media_seek(direction),
-        ActionMode::Scroll => execute_scroll(direction),
-        ActionMode::Brightness => execute_brightness(direction),
-        ActionMode::Custom => execute_custom(direction, settings),
-    }
-}
-
-fn execute_volume(direction: i8) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let sign = if direction > 0 { "+" } else { "-" };
-    let cmd = format!("amixer sset Master 5%{}", sign);
-    log::info!("Volume: {}", cmd);
-    
-    let output = Command::new("sh").arg("-c").arg(&cmd).output()?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).into());
-    }
-    Ok(())
-}
-
-fn execute_media_track(direction: i8) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let cmd = if direction > 0 { "playerctl next" } else { "playerctl previous" };
-    log::info!("Media: {}", cmd);
-    
-    let output = Command::new("sh").arg("-c").arg(cmd).output()?;
-    if !output.status.success() {
-        log::debug!("playerctl: {}", String::from_utf8_lossy(&output.stderr));
-    }
-    Ok(())
-}
-
-fn execute_media_seek(direction: i8) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let cmd = if direction > 0 { "playerctl position 5+" } else { "playerctl position 5-" };
-    log::info!("Seek: {}", cmd);
-    
-    let output = Command::new("sh").arg("-c").arg(cmd).output()?;
-    if !output.status.success() {
-        log::debug!("playerctl seek: {}", String::from_utf8_lossy(&output.stderr));
-    }
-    Ok(())
-}
-
-fn execute_scroll(direction: i8) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let button = if direction > 0 { 5 } else { 4 }; // 5=down, 4=up
-    let cmd = format!("xdotool click --repeat 3 {}", button);
-    log::info!("Scroll: {}", cmd);
-    
-    let output = Command::new("sh").arg("-c").arg(&cmd).output()?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).into());
-    }
-    Ok(())
-}
 
-fn execute_brightness(direction: i8) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let sign = if direction > 0 { "+" } else { "-" };
-    let cmd = format!("brightnessctl set 10%{}", sign);
-    log::info!("Brightness: {}", cmd);
-    
-    let output = Command::new("sh").arg("-c").arg(&cmd).output()?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).into());
-    }
-    Ok(())
-}
-
-fn execute_custom(direction: i8, settings: &RotateSettings) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let cmd = if direction > 0 { &settings.cw_command } else { &settings.ccw_command };
-    if cmd.is_empty() {
-        return Ok(());
-    }
-    log::info!("Custom: {}", cmd);
-    
-    let output = Command::new("sh").arg("-c").arg(cmd).output()?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).into());
+    async fn send_to_plugin(
+        &self,
+        instance: &Instance,
+        settings: &Self::Settings,
+        payload: &serde_json::Value,
+    ) -> OpenActionResult<()> {
+        handle_inspector_message(instance, settings, payload).await
     }
-    Ok(())
 }
 
 // ============================================================================
@@ -282,16 +1582,14 @@ fn execute_custom(direction: i8, settings: &RotateSettings) -> Result<(), Box<dy
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    simplelog::TermLogger::init(
-        simplelog::LevelFilter::Info,
-        simplelog::Config::default(),
-        simplelog::TerminalMode::Stdout,
-        simplelog::ColorChoice::Never,
-    )?;
+    logger::init(log::LevelFilter::Info)?;
     
     log::info!("========================================");
     log::info!("N1 Encoder Actions plugin starting...");
     log::info!("========================================");
+    if commands::dry_run_enabled() {
+        log::warn!("N1_DRY_RUN is set: commands are logged, not executed");
+    }
     
     // Register global handler (needs to be static)
     static GLOBAL_HANDLER: N1EncoderGlobalHandler = N1EncoderGlobalHandler;
@@ -301,9 +1599,252 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     openaction::register_action(RotateAction).await;
     openaction::register_action(PressAction).await;
     
-    // Run the plugin
-    openaction::run(std::env::args().collect()).await?;
+    // Run the plugin until OpenDeck disconnects or we are interrupted
+    tokio::select! {
+        result = openaction::run(std::env::args().collect()) => result?,
+        _ = tokio::signal::ctrl_c() => log::info!("Interrupted"),
+    }
     
     log::info!("Plugin shutting down");
+    // Drop pending long press, double press and coalescing timers so no
+    // action fires while the runtime winds down
+    STATE.clear();
+    modes::release_alt().await;
+    modes::release_color_temp().await;
+    #[cfg(unix)]
+    status::shutdown();
+    #[cfg(all(feature = "uinput", target_os = "linux"))]
+    uinput::shutdown();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mode: ActionMode) -> RotateSettings {
+        RotateSettings { mode: Some(mode), ..RotateSettings::default() }
+    }
+
+    #[test]
+    fn volume_step_is_clamped() {
+        let step = |volume_step| RotateSettings { volume_step, ..RotateSettings::default() }.volume_step();
+        assert_eq!(step(0), default_volume_step() as u32);
+        assert_eq!(step(-3), default_volume_step() as u32);
+        assert_eq!(step(1), 1);
+        assert_eq!(step(25), 25);
+        assert_eq!(step(26), 25);
+        assert_eq!(step(i32::MAX), 25);
+    }
+
+    #[test]
+    fn v0_settings_migrate() {
+        let stored = serde_json::json!({
+            "mode": "key_press",
+            "cwCommand": "echo up",
+            "ccwCommand": "echo down",
+            "cw_key": "ctrl+Right",
+            "ccw_key": "ctrl+Left",
+            "audio_backend": "pactl",
+            "press_mode": "mute",
+            "invert_dial": true,
+            "detents_per_action": 2,
+            "long_press_command": "notify-send held",
+            "volume_step": 40
+        });
+        let settings: RotateSettings = serde_json::from_value(stored).unwrap();
+        assert_eq!(settings.version, 0);
+        let migrated = settings.migrate().unwrap();
+        assert_eq!(migrated.version, SETTINGS_VERSION);
+        assert!(migrated.migrate().is_none());
+
+        assert_eq!(migrated.mode(), ActionMode::Keys);
+        assert_eq!(migrated.cw_command, "echo up");
+        assert_eq!(migrated.ccw_command, "echo down");
+        assert_eq!(migrated.cw_keys, "ctrl+Right");
+        assert_eq!(migrated.ccw_keys, "ctrl+Left");
+        assert_eq!(migrated.volume_backend, VolumeBackend::Pactl);
+        assert_eq!(migrated.press_action, DialPressAction::MuteToggle);
+        assert!(migrated.reverse);
+        assert_eq!(migrated.ticks_per_action, 2);
+        assert_eq!(migrated.long_press_action, DialPressAction::Custom);
+        assert_eq!(migrated.long_press_command, "notify-send held");
+        assert_eq!(migrated.volume_step, 25);
+        assert_eq!(migrated.mic_volume_step, default_volume_step());
+
+        // Stored back under the current names, which load as they are
+        let saved = serde_json::to_value(&migrated).unwrap();
+        for legacy in ["cwCommand", "ccwCommand", "cw_key", "ccw_key", "audio_backend", "press_mode", "invert_dial", "detents_per_action"] {
+            assert!(saved.get(legacy).is_none(), "{} still stored", legacy);
+        }
+        assert_eq!(saved["mode"], "keys");
+        let reloaded: RotateSettings = serde_json::from_value(saved).unwrap();
+        assert!(reloaded.migrate().is_none());
+        assert_eq!(reloaded.long_press_action, DialPressAction::Custom);
+    }
+
+    #[test]
+    fn v0_baseline_settings_migrate() {
+        let settings: RotateSettings = serde_json::from_str(r#"{"mode":"media_track","cw_command":"","ccw_command":""}"#).unwrap();
+        let migrated = settings.migrate().unwrap();
+        assert_eq!(migrated.mode(), ActionMode::MediaTrack);
+        assert_eq!(migrated.long_press_action, DialPressAction::None);
+        assert_eq!(migrated.volume_step, default_volume_step());
+    }
+
+    /// Multiplier for ticks arriving at `rate` per second with the default curve
+    fn multiplier(settings: &RotateSettings, rate: f32) -> u32 {
+        settings.acceleration_multiplier(Duration::from_secs_f32(1.0 / rate))
+    }
+
+    #[test]
+    fn acceleration_scales_between_the_rates() {
+        let settings = RotateSettings { acceleration: true, ..settings(ActionMode::Volume) };
+        // 5 ticks/s and below, and the first tick after a pause, get 1x
+        assert_eq!(multiplier(&settings, 0.5), 1);
+        assert_eq!(multiplier(&settings, 5.0), 1);
+        assert_eq!(multiplier(&settings, 6.0), 1);
+        assert_eq!(multiplier(&settings, 8.0), 2);
+        assert_eq!(multiplier(&settings, 10.0), 2);
+        assert_eq!(multiplier(&settings, 15.0), 3);
+        assert_eq!(multiplier(&settings, 40.0), 3);
+        // A zero interval counts as a very fast tick
+        assert_eq!(settings.acceleration_multiplier(Duration::ZERO), 3);
+    }
+
+    #[test]
+    fn acceleration_off_or_flat_is_1x() {
+        let off = settings(ActionMode::Volume);
+        assert_eq!(multiplier(&off, 40.0), 1);
+        let flat = RotateSettings { acceleration: true, acceleration_factor: 1, ..settings(ActionMode::Volume) };
+        assert_eq!(multiplier(&flat, 40.0), 1);
+        let zero = RotateSettings { acceleration_factor: 0, ..flat };
+        assert_eq!(multiplier(&zero, 40.0), 1);
+    }
+
+    #[test]
+    fn media_track_never_accelerates() {
+        let settings = RotateSettings { acceleration: true, acceleration_factor: 5, ..settings(ActionMode::MediaTrack) };
+        assert_eq!(multiplier(&settings, 40.0), 1);
+        let seek = RotateSettings { mode: Some(ActionMode::MediaSeek), ..settings };
+        assert_eq!(multiplier(&seek, 40.0), 5);
+    }
+
+    #[test]
+    fn inverted_rates_apply_the_full_factor() {
+        let settings = RotateSettings {
+            acceleration: true,
+            acceleration_min_rate: 10.0,
+            acceleration_max_rate: 10.0,
+            ..settings(ActionMode::Scroll)
+        };
+        assert_eq!(multiplier(&settings, 5.0), 1);
+        assert_eq!(multiplier(&settings, 12.0), 3);
+    }
+
+    #[test]
+    fn fast_ticks_saturate_the_magnitude() {
+        let settings = RotateSettings { acceleration: true, acceleration_factor: u32::MAX, ..settings(ActionMode::Volume) };
+        assert_eq!(settings.acceleration_multiplier(Duration::from_millis(10)), 10);
+        let state = state::PluginState::default();
+        assert_eq!(state.rotation_magnitude("dial", u32::MAX / 4, &settings), u32::MAX / 4);
+        assert_eq!(state.rotation_magnitude("dial", u32::MAX / 4, &settings), u32::MAX);
+    }
+
+    #[test]
+    fn rotations_coalesce_by_default() {
+        let window = Duration::from_millis(default_coalesce_ms());
+        let volume = settings(ActionMode::Volume);
+        assert_eq!(volume.dispatch(1, 1), Dispatch::Coalesce(1, window));
+        assert_eq!(volume.dispatch(-1, 3), Dispatch::Coalesce(-3, window));
+    }
+
+    #[test]
+    fn zero_window_submits_at_once() {
+        let volume = RotateSettings { coalesce_ms: 0, ..settings(ActionMode::Volume) };
+        assert_eq!(volume.dispatch(1, 2), Dispatch::Submit(2));
+    }
+
+    #[test]
+    fn undo_redo_never_coalesces() {
+        assert_eq!(settings(ActionMode::UndoRedo).dispatch(-1, 1), Dispatch::Submit(-1));
+    }
+
+    #[test]
+    fn invert_dial_is_reverse() {
+        let settings: RotateSettings = serde_json::from_str(r#"{"mode":"custom","invert_dial":true,"pressed_mode":"volume"}"#).unwrap();
+        assert!(settings.reverse);
+        assert_eq!(settings.direction(1), -1);
+        assert_eq!(settings.direction(-1), 1);
+        // The pressed mode turns the same way, and is not flipped back
+        let pressed = settings.pressed_settings().unwrap();
+        assert_eq!(pressed.direction(1), -1);
+        assert_eq!(pressed.dispatch(pressed.direction(2), 2), Dispatch::Coalesce(-2, Duration::from_millis(default_coalesce_ms())));
+        // Saved back under the current name
+        let saved = serde_json::to_value(&settings).unwrap();
+        assert_eq!(saved["reverse"], true);
+        assert!(saved.get("invert_dial").is_none());
+    }
+
+    #[test]
+    fn held_dial_dispatches_the_pressed_mode() {
+        let held = RotateSettings {
+            pressed_mode: Some(ActionMode::UndoRedo),
+            ..settings(ActionMode::Volume)
+        };
+        let pressed = held.pressed_settings().unwrap();
+        assert_eq!(pressed.mode(), ActionMode::UndoRedo);
+        assert_eq!(pressed.dispatch(pressed.direction(1), 1), Dispatch::Submit(1));
+        assert!(settings(ActionMode::Volume).pressed_settings().is_none());
+    }
+
+    #[test]
+    fn scroll_backend_resolves_to_a_platform_tool() {
+        let detected = ScrollBackend::Auto.tool();
+        if cfg!(windows) {
+            assert_eq!(detected, ScrollBackend::SendInput);
+        } else if cfg!(target_os = "macos") {
+            assert_eq!(detected, ScrollBackend::Quartz);
+        } else {
+            assert!(matches!(detected, ScrollBackend::Xdotool | ScrollBackend::Ydotool));
+        }
+        // Uinput always injects through the detected tool
+        assert_eq!(ScrollBackend::Uinput.tool(), detected);
+        assert_eq!(ScrollBackend::Xdotool.tool(), ScrollBackend::Xdotool);
+        assert_eq!(ScrollBackend::Ydotool.tool(), ScrollBackend::Ydotool);
+        // A native injector elsewhere falls back to the detected tool
+        let native = |backend: ScrollBackend, here: bool| if here { backend } else { detected.clone() };
+        assert_eq!(ScrollBackend::SendInput.tool(), native(ScrollBackend::SendInput, cfg!(windows)));
+        assert_eq!(ScrollBackend::Quartz.tool(), native(ScrollBackend::Quartz, cfg!(target_os = "macos")));
+    }
+
+    #[tokio::test]
+    async fn down_twist_up_rotates_the_pressed_mode_without_pressing() {
+        let state = state::PluginState::default();
+        let held = RotateSettings {
+            pressed_mode: Some(ActionMode::MediaSeek),
+            press_debounce_ms: 0,
+            ..settings(ActionMode::Volume)
+        };
+
+        // Down: the press is recorded and its long press timer starts
+        assert!(state.press_down("dial", &held));
+        let long_press = tokio::spawn(std::future::pending::<()>());
+        let long_press_abort = long_press.abort_handle();
+        state.start_long_press("dial", long_press);
+
+        // Twist: even without the pressed flag, the held dial turns in the pressed mode
+        assert!(state.rotate_while_held("dial", false));
+        assert_eq!(held.pressed_settings().unwrap().mode(), ActionMode::MediaSeek);
+        tokio::task::yield_now().await;
+        assert!(long_press_abort.is_finished());
+
+        // Up: the turn used up the press
+        assert!(!state.press_up("dial"));
+
+        // Released, the dial turns normally and a plain press goes through
+        assert!(!state.rotate_while_held("dial", false));
+        assert!(state.press_down("dial", &held));
+        assert!(state.press_up("dial"));
+    }
+}
@@ -0,0 +1,88 @@
+/*!
+ * Volume backend on the ALSA mixer API, built with the `alsa` feature
+ *
+ * Changes the element in-process instead of forking amixer per tick. The
+ * mixer handle is kept in the plugin state between calls. Elements are
+ * driven on their playback side, or their capture side for the microphone.
+ */
+
+use crate::error::ActionError;
+use crate::state::STATE;
+use alsa::mixer::{Selem, SelemChannelId, SelemId};
+
+/// Change `element` by `delta` percent of its range and return the new level
+/// in percent. Like `amixer sset`, the step is on the raw volume range.
+pub(crate) fn change_volume(element: &str, capture: bool, delta: i64) -> Result<u32, ActionError> {
+    let level = STATE.with_mixer(|mixer| {
+        mixer.handle_events()?;
+        let selem = find(mixer, element, |s| if capture { s.has_capture_volume() } else { s.has_playback_volume() })?;
+        let (min, max) = if capture { selem.get_capture_volume_range() } else { selem.get_playback_volume_range() };
+        let current = if capture {
+            selem.get_capture_volume(SelemChannelId::mono())?
+        } else {
+            selem.get_playback_volume(SelemChannelId::mono())?
+        };
+        let target = (current + delta * (max - min) / 100).clamp(min, max);
+        if capture {
+            selem.set_capture_volume_all(target)?;
+        } else {
+            selem.set_playback_volume_all(target)?;
+        }
+        Ok(((target - min) * 100 / (max - min).max(1)) as u32)
+    });
+    level.map_err(|e| ActionError::Mixer(format!("{} (element '{}')", e, element)))
+}
+
+/// Current level of `element` in percent
+pub(crate) fn volume(element: &str, capture: bool) -> Result<u32, ActionError> {
+    let level = STATE.with_mixer(|mixer| {
+        mixer.handle_events()?;
+        let selem = find(mixer, element, |s| if capture { s.has_capture_volume() } else { s.has_playback_volume() })?;
+        let (min, max) = if capture { selem.get_capture_volume_range() } else { selem.get_playback_volume_range() };
+        let current = if capture {
+            selem.get_capture_volume(SelemChannelId::mono())?
+        } else {
+            selem.get_playback_volume(SelemChannelId::mono())?
+        };
+        Ok(((current - min) * 100 / (max - min).max(1)) as u32)
+    });
+    level.map_err(|e| ActionError::Mixer(format!("{} (element '{}')", e, element)))
+}
+
+/// Whether the playback (or capture) switch of `element` is off
+pub(crate) fn muted(element: &str, capture: bool) -> Result<bool, ActionError> {
+    let muted = STATE.with_mixer(|mixer| {
+        mixer.handle_events()?;
+        let selem = find(mixer, element, |s| if capture { s.has_capture_switch() } else { s.has_playback_switch() })?;
+        let on = if capture {
+            selem.get_capture_switch(SelemChannelId::mono())?
+        } else {
+            selem.get_playback_switch(SelemChannelId::mono())?
+        };
+        Ok(on == 0)
+    });
+    muted.map_err(|e| ActionError::Mixer(format!("{} (element '{}')", e, element)))
+}
+
+/// Flip the playback (or capture) switch of `element`
+pub(crate) fn toggle_mute(element: &str, capture: bool) -> Result<(), ActionError> {
+    let toggled = STATE.with_mixer(|mixer| {
+        mixer.handle_events()?;
+        let selem = find(mixer, element, |s| if capture { s.has_capture_switch() } else { s.has_playback_switch() })?;
+        if capture {
+            let on = selem.get_capture_switch(SelemChannelId::mono())?;
+            selem.set_capture_switch_all(if on == 0 { 1 } else { 0 })
+        } else {
+            let on = selem.get_playback_switch(SelemChannelId::mono())?;
+            selem.set_playback_switch_all(if on == 0 { 1 } else { 0 })
+        }
+    });
+    toggled.map_err(|e| ActionError::Mixer(format!("{} (element '{}')", e, element)))
+}
+
+fn find<'a>(mixer: &'a alsa::Mixer, element: &str, usable: impl Fn(&Selem) -> bool) -> alsa::Result<Selem<'a>> {
+    mixer
+        .find_selem(&SelemId::new(element, 0))
+        .filter(|s| usable(s))
+        .ok_or_else(|| alsa::Error::unsupported("find_selem"))
+}
@@ -0,0 +1,2630 @@
+/*!
+ * Rotation modes as pluggable executors
+ *
+ * Each `ActionMode` is served by one `ActionExecutor`, looked up in a
+ * registry keyed by mode. Adding a mode means adding a struct here and an
+ * entry in `EXECUTORS`; the process plumbing they share lives in `commands`.
+ */
+
+use crate::commands::{CommandContext, custom_command, output, run, run_shell, shell, shell_quote, sync_mute};
+use crate::error::ActionError;
+use crate::keyspec::{self, KeyChord};
+use crate::state::STATE;
+use crate::{ActionMode, MouseAxis, MpdTarget, RedoKeys, RotateSettings, ScrollBackend, SeekMode, TabKeys, VolumeBackend, WorkspaceBackend, ZoomStrategy};
+use openaction::async_trait;
+use std::collections::HashMap;
+use std::process::Output;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+/// One rotation mode
+#[async_trait]
+pub(crate) trait ActionExecutor: Send + Sync {
+    /// Apply `delta` signed steps; positive is clockwise
+    async fn rotate(&self, delta: i32, context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError>;
+
+    /// The mode's toggle, e.g. mute for Volume and MicVolume. Does nothing
+    /// by default.
+    async fn press(&self, _context: &CommandContext, _settings: &RotateSettings) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    /// Level of the mode's target in percent after a rotation, for modes
+    /// and backends that can report one
+    async fn level(&self, _settings: &RotateSettings) -> Option<u32> {
+        None
+    }
+
+    /// Title to show on the dial after a rotation, e.g. the output device
+    /// switched to; takes the place of the level overlay
+    async fn title(&self, _settings: &RotateSettings) -> Option<String> {
+        None
+    }
+
+    /// Whether the mode's target is muted, for backends that can tell
+    async fn muted(&self, _context: &CommandContext, _settings: &RotateSettings) -> Option<bool> {
+        None
+    }
+
+    /// Why the mode cannot run in this session, judging by the tools found
+    /// at startup; None if it can
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        None
+    }
+}
+
+static EXECUTORS: LazyLock<HashMap<ActionMode, Box<dyn ActionExecutor>>> = LazyLock::new(|| {
+    let mut executors: HashMap<ActionMode, Box<dyn ActionExecutor>> = HashMap::new();
+    executors.insert(ActionMode::Volume, Box::new(Volume::new(AudioTarget::Output)));
+    executors.insert(ActionMode::MicVolume, Box::new(Volume::new(AudioTarget::Input)));
+    executors.insert(ActionMode::SinkSwitch, Box::new(SinkSwitch));
+    executors.insert(ActionMode::MediaTrack, Box::new(MediaTrack));
+    executors.insert(ActionMode::MediaSeek, Box::new(MediaSeek));
+    executors.insert(ActionMode::PlaybackRate, Box::new(PlaybackRate));
+    executors.insert(ActionMode::PlayerSelect, Box::new(PlayerSelect));
+    executors.insert(ActionMode::Scroll, Box::new(Scroll(Axis::Vertical)));
+    executors.insert(ActionMode::HScroll, Box::new(Scroll(Axis::Horizontal)));
+    executors.insert(ActionMode::MouseMove, Box::new(MouseMove));
+    executors.insert(ActionMode::Brightness, Box::new(Brightness));
+    executors.insert(ActionMode::MonitorBrightness, Box::new(MonitorBrightness));
+    executors.insert(ActionMode::ColorTemp, Box::new(ColorTemp));
+    executors.insert(ActionMode::Zoom, Box::new(Zoom));
+    executors.insert(ActionMode::Workspace, Box::new(Workspace));
+    executors.insert(ActionMode::WindowSwitch, Box::new(WindowSwitch));
+    executors.insert(ActionMode::TabSwitch, Box::new(TabSwitch));
+    executors.insert(ActionMode::UndoRedo, Box::new(UndoRedo));
+    executors.insert(ActionMode::Pan, Box::new(Pan));
+    executors.insert(ActionMode::Keys, Box::new(Keys));
+    executors.insert(ActionMode::Obs, Box::new(Obs));
+    executors.insert(ActionMode::Mpd, Box::new(Mpd));
+    executors.insert(ActionMode::HomeAssistant, Box::new(HomeAssistant));
+    executors.insert(ActionMode::Webhook, Box::new(Webhook));
+    executors.insert(ActionMode::Custom, Box::new(Custom));
+    executors
+});
+
+/// The executor registered for `mode`
+pub(crate) fn executor(mode: &ActionMode) -> &'static dyn ActionExecutor {
+    EXECUTORS[mode].as_ref()
+}
+
+/// Reason for a mode with no backend on this platform
+fn unsupported(name: &str) -> Option<String> {
+    Some(format!("{} mode is unsupported on this platform", name))
+}
+
+/// Split a signed delta into the direction (1 or -1) and step count
+fn split(delta: i32) -> (i8, u32) {
+    (if delta > 0 { 1 } else { -1 }, delta.unsigned_abs())
+}
+
+// ============================================================================
+// Volume
+// ============================================================================
+
+/// Which end of the default audio path a volume executor drives
+#[derive(Clone, Copy, PartialEq)]
+enum AudioTarget {
+    /// The default sink, for Volume mode
+    Output,
+    /// The default source, for MicVolume mode
+    Input,
+}
+
+impl AudioTarget {
+    fn name(self) -> &'static str {
+        match self {
+            AudioTarget::Output => "Volume",
+            AudioTarget::Input => "Mic",
+        }
+    }
+
+    /// The mode driving this target, which keys its mute state
+    fn mode(self) -> ActionMode {
+        match self {
+            AudioTarget::Output => ActionMode::Volume,
+            AudioTarget::Input => ActionMode::MicVolume,
+        }
+    }
+
+    #[cfg(any(feature = "alsa", windows))]
+    fn capture(self) -> bool {
+        self == AudioTarget::Input
+    }
+
+    /// Percent to move for `magnitude` steps, saturating for magnitudes
+    /// too large to multiply; the rails clamp it further
+    fn step(self, magnitude: u32, settings: &RotateSettings) -> u32 {
+        let step = match self {
+            AudioTarget::Output => settings.volume_step(),
+            AudioTarget::Input => settings.mic_volume_step(),
+        };
+        step.saturating_mul(magnitude)
+    }
+
+    /// Mixer element for the amixer and alsa backends
+    fn element(self, settings: &RotateSettings) -> &str {
+        match self {
+            AudioTarget::Output => &settings.mixer_element,
+            AudioTarget::Input => &settings.mic_element,
+        }
+    }
+
+    fn wpctl_node(self) -> &'static str {
+        match self {
+            AudioTarget::Output => "@DEFAULT_AUDIO_SINK@",
+            AudioTarget::Input => "@DEFAULT_AUDIO_SOURCE@",
+        }
+    }
+
+    /// pactl object kind and its default alias, e.g. ("sink", "@DEFAULT_SINK@")
+    fn pactl_node(self) -> (&'static str, &'static str) {
+        match self {
+            AudioTarget::Output => ("sink", "@DEFAULT_SINK@"),
+            AudioTarget::Input => ("source", "@DEFAULT_SOURCE@"),
+        }
+    }
+
+    /// AppleScript volume setting name
+    fn osascript(self) -> &'static str {
+        match self {
+            AudioTarget::Output => "output",
+            AudioTarget::Input => "input",
+        }
+    }
+}
+
+/// Volume and MicVolume: the same backends on the default sink or source
+struct Volume {
+    target: AudioTarget,
+    /// Level and, where known, mute state the last rotation left the
+    /// target at, worked out from the read that clamped its step, so
+    /// `level` need not read it again
+    rotated_to: Mutex<Option<(u32, Option<bool>)>>,
+}
+
+/// Shell command stepping `target` by `step` percent in `direction` with a
+/// command-line mixer
+fn volume_command(target: AudioTarget, backend: &VolumeBackend, element: &str, direction: i8, step: u32) -> String {
+    let sign = if direction > 0 { "+" } else { "-" };
+    let (kind, alias) = target.pactl_node();
+    match backend {
+        VolumeBackend::Auto | VolumeBackend::Amixer | VolumeBackend::Alsa | VolumeBackend::Windows | VolumeBackend::Osascript => {
+            format!("amixer sset {} {}%{}", shell_quote(element), step, sign)
+        }
+        VolumeBackend::Wpctl => format!("wpctl set-volume {} {}%{}", target.wpctl_node(), step, sign),
+        VolumeBackend::Pactl => format!("pactl set-{}-volume {} {}{}%", kind, alias, sign, step),
+    }
+}
+
+#[async_trait]
+impl ActionExecutor for Volume {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let target = self.target;
+        *self.rotated_to.lock().unwrap() = None;
+        let (direction, magnitude) = split(delta);
+        let sign = if direction > 0 { "+" } else { "-" };
+        let step = target.step(magnitude, settings);
+        let element = target.element(settings);
+        let backend = settings.volume_backend.resolve();
+        #[cfg(feature = "alsa")]
+        if backend == VolumeBackend::Alsa {
+            if crate::commands::dry_run() {
+                log::info!("{}: ALSA {} {}%{}", target.name(), element, step, sign);
+                return Ok(());
+            }
+            let started = std::time::Instant::now();
+            let level = crate::mixer::change_volume(element, target.capture(), i64::from(direction) * i64::from(step))?;
+            log::info!("{}: ALSA {} {}%{}, now {}%", target.name(), element, step, sign, level);
+            log::debug!("ALSA volume change took {:?}", started.elapsed());
+            return Ok(());
+        }
+        #[cfg(windows)]
+        if backend == VolumeBackend::Windows {
+            if crate::commands::dry_run() {
+                log::info!("{}: Windows {}%{}", target.name(), step, sign);
+                return Ok(());
+            }
+            let level = crate::win32::change_volume(target.capture(), i64::from(direction) * i64::from(step))?;
+            log::info!("{}: Windows {}%{}, now {}%", target.name(), step, sign, level);
+            return Ok(());
+        }
+        if backend == VolumeBackend::Osascript {
+            // Read and set in one script so the step is relative
+            let side = target.osascript();
+            let script = format!("set volume {} volume (({} volume of (get volume settings)) {} {})", side, side, sign, step);
+            log::info!("{}: osascript -e '{}'", target.name(), script);
+            let mut cmd = Command::new("osascript");
+            cmd.arg("-e").arg(&script);
+            return run(cmd, settings).await;
+        }
+        // Read the level first so the step stops at 0% and 100%: pactl and
+        // wpctl would go past 100%, and a step at the rail is a wasted spawn.
+        // The same read gives the level afterwards.
+        let (step, after) = match read_level(target, &backend, settings).await {
+            Some((current, muted)) => {
+                let room = if direction > 0 { 100u32.saturating_sub(current) } else { current };
+                if room == 0 {
+                    log::debug!("{} already at {}%, nothing to do", target.name(), current);
+                    *self.rotated_to.lock().unwrap() = Some((current, muted));
+                    return Ok(());
+                }
+                let step = step.min(room);
+                let level = if direction > 0 { current + step } else { current - step };
+                (step, Some((level, muted)))
+            }
+            None => (step, None),
+        };
+        let cmd = volume_command(target, &backend, element, direction, step);
+        log::info!("{}: {}", target.name(), cmd);
+
+        let started = std::time::Instant::now();
+        run(shell(&cmd), settings).await?;
+        log::debug!("Volume command took {:?}", started.elapsed());
+        *self.rotated_to.lock().unwrap() = after;
+        Ok(())
+    }
+
+    async fn level(&self, settings: &RotateSettings) -> Option<u32> {
+        let target = self.target;
+        let backend = settings.volume_backend.resolve();
+        #[cfg(feature = "alsa")]
+        if backend == VolumeBackend::Alsa {
+            let element = target.element(settings);
+            if let Ok(muted) = crate::mixer::muted(element, target.capture()) {
+                sync_mute(&target.mode(), muted).await;
+            }
+            return crate::mixer::volume(element, target.capture()).ok();
+        }
+        #[cfg(windows)]
+        if backend == VolumeBackend::Windows {
+            if let Ok(muted) = crate::win32::muted(target.capture()) {
+                sync_mute(&target.mode(), muted).await;
+            }
+            return crate::win32::volume(target.capture()).ok();
+        }
+        let rotated_to = self.rotated_to.lock().unwrap().take();
+        let (percent, muted) = match rotated_to {
+            Some(level) => level,
+            None => read_volume(target, &backend, settings).await.map(|(percent, muted)| (percent, Some(muted)))?,
+        };
+        log::info!("{} now {}%{}", target.name(), percent, if muted == Some(true) { " (muted)" } else { "" });
+        // Catch mutes made elsewhere while we are at it
+        if let Some(muted) = muted {
+            sync_mute(&target.mode(), muted).await;
+        }
+        Some(percent)
+    }
+
+    /// Toggle mute on the configured backend. The state is read before and
+    /// after so dials showing it stay in step; on backends that cannot
+    /// report it the toggle is fire-and-forget.
+    async fn press(&self, context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let target = self.target;
+        let before = self.muted(context, settings).await;
+        self.toggle_mute(settings).await?;
+        if let Some(muted) = self.muted(context, settings).await.or(before.map(|muted| !muted)) {
+            log::info!("{} now {}", target.name(), if muted { "muted" } else { "unmuted" });
+            sync_mute(&target.mode(), muted).await;
+        }
+        Ok(())
+    }
+
+    async fn muted(&self, _context: &CommandContext, settings: &RotateSettings) -> Option<bool> {
+        let target = self.target;
+        let backend = settings.volume_backend.resolve();
+        #[cfg(feature = "alsa")]
+        if backend == VolumeBackend::Alsa {
+            return crate::mixer::muted(target.element(settings), target.capture()).ok();
+        }
+        #[cfg(windows)]
+        if backend == VolumeBackend::Windows {
+            return crate::win32::muted(target.capture()).ok();
+        }
+        read_volume(target, &backend, settings).await.map(|(_, muted)| muted)
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        let capabilities = STATE.capabilities();
+        let builtin = cfg!(windows) || (cfg!(feature = "alsa") && settings.volume_backend == VolumeBackend::Alsa);
+        let tools = ["wpctl", "pactl", "amixer", "osascript"];
+        (!builtin && !tools.iter().any(|tool| capabilities.has(tool)))
+            .then(|| format!("{} mode needs wpctl, pactl or amixer; none found", self.target.name()))
+    }
+}
+
+impl Volume {
+    fn new(target: AudioTarget) -> Self {
+        Self { target, rotated_to: Mutex::new(None) }
+    }
+
+    /// Flip mute on the configured backend without reading it back
+    async fn toggle_mute(&self, settings: &RotateSettings) -> Result<(), ActionError> {
+        let target = self.target;
+        let element = target.element(settings);
+        let backend = settings.volume_backend.resolve();
+        #[cfg(feature = "alsa")]
+        if backend == VolumeBackend::Alsa {
+            log::info!("{} mute: ALSA {} toggle", target.name(), element);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::mixer::toggle_mute(element, target.capture());
+        }
+        #[cfg(windows)]
+        if backend == VolumeBackend::Windows {
+            log::info!("{} mute: Windows toggle", target.name());
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::win32::toggle_mute(target.capture()).map(|_| ());
+        }
+        let (kind, alias) = target.pactl_node();
+        let cmd = match backend {
+            VolumeBackend::Auto | VolumeBackend::Amixer | VolumeBackend::Alsa | VolumeBackend::Windows => {
+                format!("amixer sset {} toggle", shell_quote(element))
+            }
+            VolumeBackend::Osascript if target == AudioTarget::Input => {
+                return Err(ActionError::Unavailable("Mic mute is unsupported on macOS".to_string()));
+            }
+            VolumeBackend::Osascript => {
+                "osascript -e 'set volume output muted not (output muted of (get volume settings))'".to_string()
+            }
+            VolumeBackend::Wpctl => format!("wpctl set-mute {} toggle", target.wpctl_node()),
+            VolumeBackend::Pactl => format!("pactl set-{}-mute {} toggle", kind, alias),
+        };
+        log::info!("{} mute: {}", target.name(), cmd);
+        run_shell(&cmd, settings).await
+    }
+}
+
+/// Current default sink or source volume in percent and whether it is
+/// muted, for backends that can report it. The node is always addressed by
+/// its @DEFAULT_…@ alias, so names with spaces never reach an argument.
+async fn read_volume(target: AudioTarget, backend: &VolumeBackend, settings: &RotateSettings) -> Option<(u32, bool)> {
+    let (percent, muted) = read_level(target, backend, settings).await?;
+    if let Some(muted) = muted {
+        return Some((percent, muted));
+    }
+    // Only pactl leaves it out
+    let (kind, alias) = target.pactl_node();
+    let mut mute = Command::new("pactl");
+    mute.arg(format!("get-{}-mute", kind)).arg(alias);
+    let muted = String::from_utf8_lossy(&output(mute, settings).await.ok()?.stdout).trim() == "Mute: yes";
+    Some((percent, muted))
+}
+
+/// Like `read_volume` with one command: the mute state is only given where
+/// the same output carries it, so not for pactl
+async fn read_level(target: AudioTarget, backend: &VolumeBackend, settings: &RotateSettings) -> Option<(u32, Option<bool>)> {
+    let text = |output: Output| String::from_utf8_lossy(&output.stdout).into_owned();
+    let with_mute = |(percent, muted)| (percent, Some(muted));
+    match backend {
+        VolumeBackend::Wpctl => {
+            let mut get = Command::new("wpctl");
+            get.arg("get-volume").arg(target.wpctl_node());
+            parse_wpctl_volume(&text(output(get, settings).await.ok()?)).map(with_mute)
+        }
+        VolumeBackend::Pactl => {
+            let (kind, alias) = target.pactl_node();
+            let mut get = Command::new("pactl");
+            get.arg(format!("get-{}-volume", kind)).arg(alias);
+            Some((parse_pactl_volume(&text(output(get, settings).await.ok()?))?, None))
+        }
+        VolumeBackend::Osascript => {
+            // AppleScript has no input mute, so the microphone never reads as muted
+            let muted = match target {
+                AudioTarget::Output => "output muted of s",
+                AudioTarget::Input => "false",
+            };
+            let mut get = Command::new("osascript");
+            get.arg("-e")
+                .arg("set s to get volume settings")
+                .arg("-e")
+                .arg(format!("return ({} volume of s as text) & \" \" & ({} as text)", target.osascript(), muted));
+            let text = text(output(get, settings).await.ok()?);
+            let (percent, muted) = text.trim().split_once(' ')?;
+            Some((percent.parse().ok()?, Some(muted == "true")))
+        }
+        VolumeBackend::Auto | VolumeBackend::Amixer => {
+            let mut get = Command::new("amixer");
+            get.arg("sget").arg(target.element(settings));
+            parse_amixer_volume(&text(output(get, settings).await.ok()?)).map(with_mute)
+        }
+        VolumeBackend::Alsa | VolumeBackend::Windows => None,
+    }
+}
+
+/// Parse `wpctl get-volume` output such as "Volume: 0.45 [MUTED]" into a
+/// percentage and mute flag
+fn parse_wpctl_volume(output: &str) -> Option<(u32, bool)> {
+    let rest = output.trim().strip_prefix("Volume:")?;
+    let volume: f32 = rest.split_whitespace().next()?.parse().ok()?;
+    Some(((volume * 100.0).round() as u32, rest.contains("[MUTED]")))
+}
+
+/// Parse the first channel's percentage from `pactl get-sink-volume`, e.g.
+/// "Volume: front-left: 42598 /  65% / -11.23 dB,   front-right: ..."
+fn parse_pactl_volume(output: &str) -> Option<u32> {
+    output
+        .split('/')
+        .map(str::trim)
+        .find_map(|field| field.strip_suffix('%'))
+        .and_then(|percent| percent.trim().parse().ok())
+}
+
+/// Parse the first channel line of `amixer sget`, e.g.
+/// "  Front Left: Playback 45000 [69%] [-10.00dB] [on]". Elements without
+/// a switch report no [on]/[off] and count as unmuted.
+fn parse_amixer_volume(output: &str) -> Option<(u32, bool)> {
+    output.lines().find_map(|line| {
+        let percent = line.split('[').find_map(|field| field.split_once("%]").map(|(percent, _)| percent))?.parse().ok()?;
+        Some((percent, line.contains("[off]")))
+    })
+}
+
+// ============================================================================
+// Output Device
+// ============================================================================
+
+/// A sink from `pactl list sinks`
+struct Sink {
+    name: String,
+    description: String,
+}
+
+/// Cycle the default sink through the available ones, moving the playing
+/// streams along, and jump to the favorite sink on press. The sink list is
+/// read afresh every time, so devices plugged in since are picked up.
+struct SinkSwitch;
+
+#[async_trait]
+impl ActionExecutor for SinkSwitch {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let exclude = sink_exclude(settings);
+        let sinks: Vec<Sink> = list_sinks(settings)
+            .await?
+            .into_iter()
+            .filter(|sink| !exclude.as_ref().is_some_and(|re| re.is_match(&sink.name) || re.is_match(&sink.description)))
+            .collect();
+        if sinks.is_empty() {
+            log::warn!("Output Device: no sinks to switch to");
+            return Ok(());
+        }
+        let current = default_sink(settings).await;
+        let count = sinks.len() as i64;
+        let next = match sinks.iter().position(|sink| Some(&sink.name) == current.as_ref()) {
+            Some(index) => (index as i64 + i64::from(delta)).rem_euclid(count),
+            // The default is excluded or unknown: start from either end
+            None if delta > 0 => 0,
+            None => count - 1,
+        };
+        switch_sink(&sinks[next as usize], settings).await
+    }
+
+    /// Switch to `favorite_sink`, matched by name or description. The
+    /// exclude filter does not apply.
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let favorite = settings
+            .favorite_sink
+            .as_deref()
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .ok_or(ActionError::EmptyCommand)?;
+        let sinks = list_sinks(settings).await?;
+        let sink = sinks
+            .iter()
+            .find(|sink| sink.name == favorite || sink.description == favorite)
+            .ok_or_else(|| ActionError::Unavailable(format!("Favorite sink '{}' not found", favorite)))?;
+        switch_sink(sink, settings).await
+    }
+
+    /// Description of the default sink, so the dial names where the audio went
+    async fn title(&self, settings: &RotateSettings) -> Option<String> {
+        let current = default_sink(settings).await?;
+        let sinks = list_sinks(settings).await.ok()?;
+        sinks.into_iter().find(|sink| sink.name == current).map(|sink| sink.description)
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Output Device");
+        }
+        (!STATE.capabilities().has("pactl")).then(|| "Output Device mode needs pactl; it was not found".to_string())
+    }
+}
+
+/// A pactl invocation with untranslated output, as the parsers expect
+fn pactl(args: &[&str]) -> Command {
+    let mut cmd = Command::new("pactl");
+    cmd.env("LC_ALL", "C").args(args);
+    cmd
+}
+
+async fn list_sinks(settings: &RotateSettings) -> Result<Vec<Sink>, ActionError> {
+    let output = output(pactl(&["list", "sinks"]), settings).await?;
+    if !output.status.success() {
+        return Err(ActionError::exit("pactl", output.status.code(), &output.stderr));
+    }
+    Ok(parse_sinks(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Pick the name and description of each sink out of `pactl list sinks`,
+/// whose blocks start with "Sink #N" followed by indented "Name: ..." and
+/// "Description: ..." lines
+fn parse_sinks(output: &str) -> Vec<Sink> {
+    let mut sinks = Vec::new();
+    let mut name = None;
+    for line in output.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("Name: ") {
+            name = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("Description: ")
+            && let Some(name) = name.take()
+        {
+            sinks.push(Sink { name, description: value.to_string() });
+        }
+    }
+    sinks
+}
+
+/// Name of the default sink, from the "Default Sink:" line of `pactl info`
+async fn default_sink(settings: &RotateSettings) -> Option<String> {
+    let output = output(pactl(&["info"]), settings).await.ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("Default Sink: "))
+        .map(|name| name.trim().to_string())
+}
+
+/// Make `sink` the default and move every playing stream onto it. A stream
+/// that ends while it is being moved is not an error.
+async fn switch_sink(sink: &Sink, settings: &RotateSettings) -> Result<(), ActionError> {
+    log::info!("Output Device: {} ({})", sink.description, sink.name);
+    run(pactl(&["set-default-sink", &sink.name]), settings).await?;
+
+    let inputs = output(pactl(&["list", "short", "sink-inputs"]), settings).await?;
+    for id in String::from_utf8_lossy(&inputs.stdout).lines().filter_map(|line| line.split_whitespace().next()) {
+        if let Err(e) = run(pactl(&["move-sink-input", id, &sink.name]), settings).await {
+            log::debug!("Output Device: moving stream {}: {}", id, e);
+        }
+    }
+    Ok(())
+}
+
+/// The compiled `sink_exclude` pattern; an invalid one is logged and ignored
+fn sink_exclude(settings: &RotateSettings) -> Option<regex::Regex> {
+    let pattern = settings.sink_exclude.as_deref().filter(|p| !p.trim().is_empty())?;
+    regex::Regex::new(pattern)
+        .inspect_err(|e| log::warn!("Ignoring invalid sink_exclude {:?}: {}", pattern, e))
+        .ok()
+}
+
+// ============================================================================
+// Workspace
+// ============================================================================
+
+/// Step through the workspaces of the focused output in sway or i3, or the
+/// open workspaces in Hyprland, and go back to the previous one on press
+struct Workspace;
+
+#[async_trait]
+impl ActionExecutor for Workspace {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let backend = workspace_backend(settings)?;
+        let (direction, magnitude) = split(delta);
+        let steps = if settings.workspace_wrap {
+            magnitude
+        } else {
+            // The compositors wrap on their own, so stop short of the end
+            match workspace_position(backend, settings).await {
+                Some((index, count)) => {
+                    let room = if direction > 0 { count - 1 - index } else { index };
+                    magnitude.min(u32::try_from(room).unwrap_or(u32::MAX))
+                }
+                None => {
+                    log::debug!("Workspace: cannot tell the focused workspace, not clamping");
+                    magnitude
+                }
+            }
+        };
+        let args: &[&str] = match (backend, direction > 0) {
+            (WorkspaceBackend::Hyprland, true) => &["dispatch", "workspace", "e+1"],
+            (WorkspaceBackend::Hyprland, false) => &["dispatch", "workspace", "e-1"],
+            (_, true) => &["workspace", "next_on_output"],
+            (_, false) => &["workspace", "prev_on_output"],
+        };
+        log::info!("Workspace: {} {} x{}", backend.tool(), args.join(" "), steps);
+        for _ in 0..steps {
+            let mut cmd = Command::new(backend.tool());
+            cmd.args(args);
+            run(cmd, settings).await?;
+        }
+        Ok(())
+    }
+
+    /// Go back to the previously focused workspace
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let backend = workspace_backend(settings)?;
+        let args: &[&str] = match backend {
+            WorkspaceBackend::Hyprland => &["dispatch", "workspace", "previous"],
+            _ => &["workspace", "back_and_forth"],
+        };
+        log::info!("Workspace: {} {}", backend.tool(), args.join(" "));
+        let mut cmd = Command::new(backend.tool());
+        cmd.args(args);
+        run(cmd, settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Workspace");
+        }
+        match workspace_backend(settings) {
+            Err(ActionError::Unavailable(reason)) => Some(reason),
+            Err(_) => None,
+            Ok(backend) => (!STATE.capabilities().has(backend.tool()))
+                .then(|| format!("Workspace mode needs {}; it was not found", backend.tool())),
+        }
+    }
+}
+
+fn workspace_backend(settings: &RotateSettings) -> Result<WorkspaceBackend, ActionError> {
+    settings
+        .workspace_backend
+        .resolve()
+        .ok_or_else(|| ActionError::Unavailable("Workspace mode needs sway, i3 or Hyprland; none detected".to_string()))
+}
+
+/// Index of the focused workspace among those the next/previous commands
+/// step through, and their count: the workspaces on the focused output in
+/// sway and i3, in their listed order, and the regular ones by id in Hyprland
+async fn workspace_position(backend: WorkspaceBackend, settings: &RotateSettings) -> Option<(usize, usize)> {
+    let query = |args: &[&str]| {
+        let mut cmd = Command::new(backend.tool());
+        cmd.args(args);
+        cmd
+    };
+    let json = |output: Output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok();
+    if backend == WorkspaceBackend::Hyprland {
+        let active = json(output(query(&["-j", "activeworkspace"]), settings).await.ok()?)?["id"].as_i64()?;
+        let workspaces = json(output(query(&["-j", "workspaces"]), settings).await.ok()?)?;
+        // Special workspaces have negative ids and are not stepped through
+        let mut ids: Vec<i64> = workspaces.as_array()?.iter().filter_map(|w| w["id"].as_i64()).filter(|id| *id > 0).collect();
+        ids.sort_unstable();
+        return Some((ids.iter().position(|id| *id == active)?, ids.len()));
+    }
+    // swaymsg prints JSON when its output is not a terminal, as i3-msg always does
+    let workspaces = json(output(query(&["-t", "get_workspaces"]), settings).await.ok()?)?;
+    let workspaces = workspaces.as_array()?;
+    let focused = workspaces.iter().find(|w| w["focused"].as_bool() == Some(true))?;
+    let on_output: Vec<_> = workspaces.iter().filter(|w| w["output"] == focused["output"]).collect();
+    Some((on_output.iter().position(|w| w["name"] == focused["name"])?, on_output.len()))
+}
+
+// ============================================================================
+// Media
+// ============================================================================
+
+/// Build a playerctl invocation, pinned to the configured player if any.
+/// The player name is passed as an argument, never through a shell.
+fn playerctl(settings: &RotateSettings, args: &[&str]) -> Command {
+    let mut cmd = Command::new("playerctl");
+    if let Some(player) = settings.player() {
+        cmd.arg(format!("--player={}", player));
+    }
+    cmd.args(args);
+    cmd
+}
+
+fn media_unavailable() -> Option<String> {
+    if cfg!(any(windows, target_os = "macos")) {
+        return None;
+    }
+    (!cfg!(feature = "mpris") && !STATE.capabilities().has("playerctl"))
+        .then(|| "Media modes need playerctl; it was not found".to_string())
+}
+
+struct MediaTrack;
+
+#[async_trait]
+impl ActionExecutor for MediaTrack {
+    /// Skip one track per step
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        for _ in 0..magnitude {
+            skip_track(direction, settings).await?;
+        }
+        Ok(())
+    }
+
+    /// Toggle play/pause
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(windows)]
+        if !STATE.capabilities().has("playerctl") {
+            log::info!("Media: play/pause key");
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::win32::media_key(crate::win32::MediaKey::PlayPause);
+        }
+        #[cfg(target_os = "macos")]
+        if !STATE.capabilities().has("playerctl") {
+            log::info!("Media: play/pause key");
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::macos::media_key(crate::macos::MediaKey::PlayPause);
+        }
+        #[cfg(feature = "mpris")]
+        if let Some(result) = crate::mpris::call(settings.player().as_deref(), crate::mpris::Call::PlayPause).await {
+            return result;
+        }
+        let output = output(playerctl(settings, &["play-pause"]), settings).await?;
+        if !output.status.success() {
+            log::debug!("playerctl play-pause: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        media_unavailable()
+    }
+}
+
+async fn skip_track(direction: i8, settings: &RotateSettings) -> Result<(), ActionError> {
+    #[cfg(windows)]
+    if !STATE.capabilities().has("playerctl") {
+        use crate::win32::MediaKey;
+        log::info!("Media: {} key", if direction > 0 { "next" } else { "previous" });
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        return crate::win32::media_key(if direction > 0 { MediaKey::Next } else { MediaKey::Previous });
+    }
+    #[cfg(target_os = "macos")]
+    if !STATE.capabilities().has("playerctl") {
+        use crate::macos::MediaKey;
+        log::info!("Media: {} key", if direction > 0 { "next" } else { "previous" });
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        return crate::macos::media_key(if direction > 0 { MediaKey::Next } else { MediaKey::Previous });
+    }
+    #[cfg(feature = "mpris")]
+    {
+        let call = if direction > 0 { crate::mpris::Call::Next } else { crate::mpris::Call::Previous };
+        if let Some(result) = crate::mpris::call(settings.player().as_deref(), call).await {
+            return result;
+        }
+    }
+    let arg = if direction > 0 { "next" } else { "previous" };
+    log::info!("Media: playerctl {} (player: {:?})", arg, settings.player());
+
+    let output = output(playerctl(settings, &[arg]), settings).await?;
+    if !output.status.success() {
+        log::debug!("playerctl: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+/// Relative seeks by `seek_seconds` or `seek_percent` of the track, or jumps
+/// between fixed points of the track, per `seek_mode`. Streams have no
+/// length, so the percent modes fall back to `seek_seconds` for them.
+struct MediaSeek;
+
+#[async_trait]
+impl ActionExecutor for MediaSeek {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let percent = f64::from(settings.seek_percent.clamp(0.1, 25.0));
+        let progress = match settings.seek_mode {
+            SeekMode::Seconds => None,
+            SeekMode::Percent | SeekMode::Absolute => {
+                let progress = track_progress(settings).await;
+                if progress.is_none() {
+                    log::debug!("Seek: track length unknown, seeking by seek_seconds");
+                }
+                progress
+            }
+        };
+        let seconds = match (settings.seek_mode, progress) {
+            (SeekMode::Absolute, Some((length, position))) => {
+                // The point nearest the position, then `delta` points on;
+                // the end is held a second short so the track does not skip
+                let point = (position / length * 100.0 / percent).round() + f64::from(delta);
+                let target = (point * percent).clamp(0.0, 100.0) / 100.0 * length;
+                return seek_to(target.min(length - 1.0).max(0.0), settings).await;
+            }
+            (SeekMode::Percent, Some((length, _))) => length * percent / 100.0 * f64::from(magnitude),
+            _ => f64::from(settings.seek_seconds.clamp(1, 600)) * f64::from(magnitude),
+        };
+        #[cfg(feature = "mpris")]
+        {
+            let offset = f64::from(direction) * seconds;
+            if let Some(result) = crate::mpris::call(settings.player().as_deref(), crate::mpris::Call::Seek(offset)).await {
+                return result;
+            }
+        }
+        // playerctl takes a relative offset as "N+" / "N-"
+        let sign = if direction > 0 { "+" } else { "-" };
+        let offset = format!("{:.2}{}", seconds, sign);
+        log::info!("Seek: playerctl position {} (player: {:?})", offset, settings.player());
+
+        let output = output(playerctl(settings, &["position", &offset]), settings).await?;
+        if !output.status.success() {
+            log::debug!("playerctl seek: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    /// Windows media keys have no seek, so Media Seek stays unsupported there
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(windows) {
+            return unsupported("Media Seek");
+        }
+        media_unavailable()
+    }
+}
+
+/// Length of the current track and the position in it, in seconds, over
+/// MPRIS or from playerctl. None for streams and players that do not say.
+async fn track_progress(settings: &RotateSettings) -> Option<(f64, f64)> {
+    #[cfg(feature = "mpris")]
+    if let Some(progress) = crate::mpris::progress(settings.player().as_deref()).await {
+        return Some(progress);
+    }
+    let read = |args: &'static [&'static str]| async move {
+        let output = output(playerctl(settings, args), settings).await.ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().ok()
+    };
+    // mpris:length is in microseconds, position in seconds
+    let length = read(&["metadata", "mpris:length"]).await? / 1e6;
+    let position = read(&["position"]).await?;
+    (length > 0.0).then_some((length, position))
+}
+
+/// Jump to `seconds` into the current track
+async fn seek_to(seconds: f64, settings: &RotateSettings) -> Result<(), ActionError> {
+    #[cfg(feature = "mpris")]
+    if let Some(result) = crate::mpris::set_position(settings.player().as_deref(), seconds).await {
+        return result;
+    }
+    let position = format!("{:.2}", seconds);
+    log::info!("Seek: playerctl position {} (player: {:?})", position, settings.player());
+    let output = output(playerctl(settings, &["position", &position]), settings).await?;
+    if !output.status.success() {
+        log::debug!("playerctl seek: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+/// Playback speed of the player over MPRIS. playerctl cannot set the rate,
+/// so this mode needs the mpris feature.
+struct PlaybackRate;
+
+#[async_trait]
+impl ActionExecutor for PlaybackRate {
+    #[cfg_attr(not(feature = "mpris"), allow(unused_variables))]
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "mpris")]
+        {
+            let step = f64::from(settings.playback_rate_step.clamp(0.01, 1.0)) * f64::from(delta);
+            if let Some(result) = crate::mpris::set_rate(settings.player().as_deref(), crate::mpris::RateChange::Step(step)).await {
+                return result;
+            }
+        }
+        Err(no_rate_player())
+    }
+
+    /// Back to normal speed
+    #[cfg_attr(not(feature = "mpris"), allow(unused_variables))]
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "mpris")]
+        if let Some(result) = crate::mpris::set_rate(settings.player().as_deref(), crate::mpris::RateChange::Reset).await {
+            return result;
+        }
+        Err(no_rate_player())
+    }
+
+    #[cfg_attr(not(feature = "mpris"), allow(unused_variables))]
+    async fn title(&self, settings: &RotateSettings) -> Option<String> {
+        #[cfg(feature = "mpris")]
+        if let Some(rate) = crate::mpris::rate(settings.player().as_deref()).await {
+            return Some(format!("{:.1}x", rate));
+        }
+        None
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Playback Rate");
+        }
+        if !cfg!(feature = "mpris") {
+            return Some("Playback Rate mode needs the plugin built with the mpris feature".to_string());
+        }
+        None
+    }
+}
+
+fn no_rate_player() -> ActionError {
+    ActionError::Unavailable("Playback Rate found no media player on the session bus".to_string())
+}
+
+/// Steps through the running players and makes the one picked the target
+/// of the media modes and play/pause on actions without a `player`. Players
+/// are picked by name without the instance suffix, so a restarted browser
+/// stays picked. Pressing goes back to whichever player is active.
+struct PlayerSelect;
+
+#[async_trait]
+impl ActionExecutor for PlayerSelect {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let players = running_players(settings).await?;
+        if players.is_empty() {
+            return Err(ActionError::Unavailable("Player Select found no running media player".to_string()));
+        }
+        // Unset or no longer running starts just before the first player
+        let current = STATE.selected_player().and_then(|selected| players.iter().position(|p| *p == selected));
+        let len = players.len() as i64;
+        let index = match current {
+            Some(index) => (index as i64 + i64::from(delta)).rem_euclid(len),
+            None if delta > 0 => i64::from(delta - 1).rem_euclid(len),
+            None => i64::from(delta).rem_euclid(len),
+        };
+        let player = players[index as usize].clone();
+        log::info!("Player Select: {} (of {:?})", player, players);
+        if !crate::commands::dry_run() {
+            STATE.select_player(Some(player));
+        }
+        Ok(())
+    }
+
+    /// Go back to the active player
+    async fn press(&self, _context: &CommandContext, _settings: &RotateSettings) -> Result<(), ActionError> {
+        log::info!("Player Select: back to the active player");
+        if !crate::commands::dry_run() {
+            STATE.select_player(None);
+        }
+        Ok(())
+    }
+
+    async fn title(&self, _settings: &RotateSettings) -> Option<String> {
+        Some(STATE.selected_player().unwrap_or_else(|| "Auto".to_string()))
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(feature = "mpris") || STATE.capabilities().has("playerctl") {
+            return None;
+        }
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Player Select");
+        }
+        Some("Player Select mode needs playerctl; it was not found".to_string())
+    }
+}
+
+/// Names of the running players without instance suffixes, sorted and
+/// without duplicates, from D-Bus or `playerctl -l`
+async fn running_players(settings: &RotateSettings) -> Result<Vec<String>, ActionError> {
+    #[cfg(feature = "mpris")]
+    let names = crate::mpris::players().await;
+    #[cfg(not(feature = "mpris"))]
+    let names: Option<Vec<String>> = None;
+    let names = match names {
+        Some(names) => names,
+        None => {
+            let mut cmd = Command::new("playerctl");
+            cmd.arg("-l");
+            let output = output(cmd, settings).await?;
+            // playerctl exits non-zero when there are no players
+            String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect()
+        }
+    };
+    let mut players: Vec<String> = names
+        .iter()
+        .map(|name| name.trim().split(".instance").next().unwrap_or_default().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    players.sort();
+    players.dedup();
+    Ok(players)
+}
+
+// ============================================================================
+// Scroll, HScroll and Zoom
+// ============================================================================
+
+/// Scroll, HScroll and Zoom need a wheel injector: the uinput device, or xdotool
+/// (X11 only) or ydotool
+fn wheel_unavailable(name: &str, settings: &RotateSettings) -> Option<String> {
+    let capabilities = STATE.capabilities();
+    if cfg!(any(windows, target_os = "macos"))
+        || (cfg!(all(feature = "uinput", target_os = "linux")) && settings.scroll_backend == ScrollBackend::Uinput)
+    {
+        None
+    } else if !capabilities.has("xdotool") && !capabilities.has("ydotool") {
+        Some(format!("{} mode needs xdotool or ydotool; neither found", name))
+    } else if settings.scroll_backend.tool() == ScrollBackend::Xdotool && !capabilities.x11 {
+        Some(format!("{} mode with xdotool needs X11 (DISPLAY is unset); install ydotool for Wayland", name))
+    } else {
+        None
+    }
+}
+
+/// Which wheel a scroll executor turns
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Axis {
+    /// The normal wheel, for Scroll mode
+    Vertical,
+    /// The tilt wheel, for HScroll mode
+    Horizontal,
+}
+
+impl Axis {
+    fn name(self) -> &'static str {
+        match self {
+            Axis::Vertical => "Scroll",
+            Axis::Horizontal => "H-Scroll",
+        }
+    }
+
+    /// Wheel clicks for `magnitude` steps: scroll_lines or hscroll_columns
+    /// each, saturating for settings too large to multiply
+    fn clicks(self, magnitude: u32, settings: &RotateSettings) -> u32 {
+        let per_tick = if self == Axis::Vertical { settings.scroll_lines } else { settings.hscroll_columns };
+        per_tick.saturating_mul(magnitude)
+    }
+}
+
+/// Scroll and HScroll: wheel clicks on the configured injector. Positive
+/// clicks scroll up or right.
+struct Scroll(Axis);
+
+#[async_trait]
+impl ActionExecutor for Scroll {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let Scroll(axis) = *self;
+        let (direction, magnitude) = split(delta);
+        let repeat = axis.clicks(magnitude, settings);
+        if repeat == 0 {
+            return Ok(());
+        }
+        // Clockwise scrolls down (or natural: up), and right
+        let positive = if axis == Axis::Vertical { (direction > 0) == settings.invert_scroll } else { direction > 0 };
+        let clicks = i32::try_from(repeat).unwrap_or(i32::MAX);
+        let clicks = if positive { clicks } else { -clicks };
+        #[cfg(all(feature = "uinput", target_os = "linux"))]
+        if settings.scroll_backend == ScrollBackend::Uinput {
+            log::info!("{}: uinput wheel {}", axis.name(), clicks);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            if let Some(result) = crate::uinput::scroll(axis, clicks).await {
+                return result;
+            }
+        }
+        #[cfg(windows)]
+        if settings.scroll_backend.tool() == ScrollBackend::SendInput {
+            log::info!("{}: SendInput wheel {}", axis.name(), clicks);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::win32::scroll(axis, clicks);
+        }
+        #[cfg(target_os = "macos")]
+        if settings.scroll_backend.tool() == ScrollBackend::Quartz {
+            log::info!("{}: Quartz wheel {}", axis.name(), clicks);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::macos::scroll(axis, clicks);
+        }
+        let cmd = match settings.scroll_backend.tool() {
+            ScrollBackend::Auto | ScrollBackend::Xdotool | ScrollBackend::Uinput | ScrollBackend::SendInput | ScrollBackend::Quartz => {
+                // 4/5 are up/down, 6/7 left/right
+                let button = match (axis, positive) {
+                    (Axis::Vertical, true) => 4,
+                    (Axis::Vertical, false) => 5,
+                    (Axis::Horizontal, false) => 6,
+                    (Axis::Horizontal, true) => 7,
+                };
+                format!("xdotool click --repeat {} {}", repeat, button)
+            }
+            // -x is the horizontal wheel, -y the vertical one
+            ScrollBackend::Ydotool if axis == Axis::Vertical => format!("ydotool mousemove --wheel -x 0 -y {}", clicks),
+            ScrollBackend::Ydotool => format!("ydotool mousemove --wheel -x {} -y 0", clicks),
+        };
+        log::info!("{}: {}", axis.name(), cmd);
+
+        run(shell(&cmd), settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        wheel_unavailable(self.0.name(), settings)
+    }
+}
+
+/// Nudges the pointer `mouse_step` pixels per tick along `mouse_axis`,
+/// right or down clockwise, e.g. for placing things precisely in image
+/// editors
+struct MouseMove;
+
+#[async_trait]
+impl ActionExecutor for MouseMove {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let pixels = i32::try_from(settings.mouse_step.min(1000)).unwrap_or(0).saturating_mul(delta);
+        if pixels == 0 {
+            return Ok(());
+        }
+        let (dx, dy) = match settings.mouse_axis {
+            MouseAxis::X => (pixels, 0),
+            MouseAxis::Y => (0, pixels),
+        };
+        #[cfg(all(feature = "uinput", target_os = "linux"))]
+        if settings.scroll_backend == ScrollBackend::Uinput {
+            log::info!("Mouse Move: uinput {} {}", dx, dy);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            if let Some(result) = crate::uinput::mouse_move(dx, dy).await {
+                return result;
+            }
+        }
+        #[cfg(windows)]
+        if settings.scroll_backend.tool() == ScrollBackend::SendInput {
+            log::info!("Mouse Move: SendInput {} {}", dx, dy);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::win32::mouse_move(dx, dy);
+        }
+        let cmd = match settings.scroll_backend.tool() {
+            // ydotool moves relative to the pointer unless --absolute
+            ScrollBackend::Ydotool => format!("ydotool mousemove -x {} -y {}", dx, dy),
+            // "--" so negative offsets are not read as options
+            _ => format!("xdotool mousemove_relative -- {} {}", dx, dy),
+        };
+        log::info!("Mouse Move: {}", cmd);
+
+        run(shell(&cmd), settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(target_os = "macos") {
+            return unsupported("Mouse Move");
+        }
+        wheel_unavailable("Mouse Move", settings)
+    }
+}
+
+/// Most key presses or zoom clicks one rotation sends. Past this a fast
+/// spin only builds a longer command line, or a ydotool event list one entry
+/// per press, without getting anywhere useful sooner.
+const MAX_KEY_REPEAT: u32 = 50;
+
+/// Ctrl + mouse wheel, or Ctrl+= / Ctrl+- with the keys strategy:
+/// clockwise zooms in, counter-clockwise zooms out. A press resets with Ctrl+0.
+struct Zoom;
+
+#[async_trait]
+impl ActionExecutor for Zoom {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let magnitude = magnitude.min(MAX_KEY_REPEAT);
+        let zoom_in = direction > 0;
+        if settings.zoom_strategy == ZoomStrategy::Keys {
+            return zoom_key(if zoom_in { ZoomKey::In } else { ZoomKey::Out }, magnitude, settings).await;
+        }
+        #[cfg(all(feature = "uinput", target_os = "linux"))]
+        if settings.scroll_backend == ScrollBackend::Uinput {
+            let clicks = i32::try_from(magnitude).unwrap_or(i32::MAX);
+            let clicks = if zoom_in { clicks } else { -clicks };
+            log::info!("Zoom: uinput ctrl+wheel {}", clicks);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            if let Some(result) = crate::uinput::zoom(clicks).await {
+                return result;
+            }
+        }
+        #[cfg(windows)]
+        if settings.scroll_backend.tool() == ScrollBackend::SendInput {
+            let clicks = i32::try_from(magnitude).unwrap_or(i32::MAX);
+            let clicks = if zoom_in { clicks } else { -clicks };
+            log::info!("Zoom: SendInput ctrl+wheel {}", clicks);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::win32::zoom(clicks);
+        }
+        let cmd = match settings.scroll_backend.tool() {
+            ScrollBackend::Auto | ScrollBackend::Xdotool | ScrollBackend::Uinput | ScrollBackend::SendInput | ScrollBackend::Quartz => {
+                let button = if zoom_in { 4 } else { 5 }; // 4=up, 5=down
+                format!("xdotool keydown ctrl click --repeat {} {} keyup ctrl", magnitude, button)
+            }
+            ScrollBackend::Ydotool => {
+                // 29 is KEY_LEFTCTRL; positive wheel values scroll up
+                let amount = if zoom_in { magnitude as i64 } else { -(magnitude as i64) };
+                format!(
+                    "ydotool key 29:1 && ydotool mousemove --wheel -x 0 -y {}; ydotool key 29:0",
+                    amount
+                )
+            }
+        };
+        log::info!("Zoom: {}", cmd);
+
+        run(shell(&cmd), settings).await
+    }
+
+    /// Reset the zoom with Ctrl+0, whichever strategy is set
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        zoom_key(ZoomKey::Reset, 1, settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(target_os = "macos") {
+            return unsupported("Zoom");
+        }
+        wheel_unavailable("Zoom", settings)
+    }
+}
+
+/// Keys of the keys zoom strategy, each sent with Ctrl held
+#[derive(Clone, Copy)]
+pub(crate) enum ZoomKey {
+    In,
+    Out,
+    Reset,
+}
+
+impl ZoomKey {
+    /// X keysym, for xdotool
+    fn keysym(self) -> &'static str {
+        match self {
+            ZoomKey::In => "equal",
+            ZoomKey::Out => "minus",
+            ZoomKey::Reset => "0",
+        }
+    }
+
+    /// Linux input event code (KEY_EQUAL, KEY_MINUS, KEY_0), for ydotool
+    /// and the uinput device
+    pub(crate) fn code(self) -> u16 {
+        match self {
+            ZoomKey::In => 13,
+            ZoomKey::Out => 12,
+            ZoomKey::Reset => 11,
+        }
+    }
+}
+
+/// Press Ctrl+`key` `repeat` times through the configured input backend
+async fn zoom_key(key: ZoomKey, repeat: u32, settings: &RotateSettings) -> Result<(), ActionError> {
+    #[cfg(all(feature = "uinput", target_os = "linux"))]
+    if settings.scroll_backend == ScrollBackend::Uinput {
+        log::info!("Zoom: uinput ctrl+{} x{}", key.keysym(), repeat);
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        if let Some(result) = crate::uinput::ctrl_key(key, repeat).await {
+            return result;
+        }
+    }
+    #[cfg(windows)]
+    if settings.scroll_backend.tool() == ScrollBackend::SendInput {
+        log::info!("Zoom: SendInput ctrl+{} x{}", key.keysym(), repeat);
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        return crate::win32::ctrl_key(key, repeat);
+    }
+    let cmd = match settings.scroll_backend.tool() {
+        ScrollBackend::Auto | ScrollBackend::Xdotool | ScrollBackend::Uinput | ScrollBackend::SendInput | ScrollBackend::Quartz => {
+            format!("xdotool key --clearmodifiers --repeat {} ctrl+{}", repeat, key.keysym())
+        }
+        ScrollBackend::Ydotool => {
+            // 29 is KEY_LEFTCTRL; one down/up pair per press
+            let press = format!("29:1 {code}:1 {code}:0 29:0", code = key.code());
+            format!("ydotool key {}", vec![press; repeat as usize].join(" "))
+        }
+    };
+    log::info!("Zoom: {}", cmd);
+
+    run(shell(&cmd), settings).await
+}
+
+// ============================================================================
+// Brightness
+// ============================================================================
+
+/// brightnessctl on Linux; on macOS the `brightness` tool (Homebrew), which
+/// only sets absolute levels, so the step is applied to a fresh reading
+struct Brightness;
+
+/// Percent to move the brightness, or a monitor's, for `magnitude` steps;
+/// more than 100 means nothing to the tools
+fn brightness_percent(magnitude: u32, settings: &RotateSettings) -> u32 {
+    settings.brightness_step.clamp(1, 100).saturating_mul(magnitude).min(100)
+}
+
+#[async_trait]
+impl ActionExecutor for Brightness {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        if cfg!(target_os = "macos") {
+            let current = macos_brightness(settings).await.unwrap_or(0.5);
+            let step = brightness_percent(magnitude, settings) as f32 / 100.0;
+            let target = (current + f32::from(direction) * step).clamp(0.0, 1.0);
+            let mut cmd = macos_brightness_command(settings);
+            cmd.arg(format!("{:.3}", target));
+            log::info!("Brightness: brightness {:.3} (display: {:?})", target, settings.brightness_device);
+            return run(cmd, settings).await;
+        }
+        let sign = if direction > 0 { "+" } else { "-" };
+        let value = format!("{}%{}", brightness_percent(magnitude, settings), sign);
+
+        let mut cmd = Command::new("brightnessctl");
+        if let Some(device) = settings.brightness_device.as_deref().filter(|d| !d.is_empty()) {
+            cmd.arg("-d").arg(device);
+        }
+        cmd.arg("set").arg(&value);
+        log::info!("Brightness: set {} (device: {:?})", value, settings.brightness_device);
+
+        run(cmd, settings).await
+    }
+
+    /// Read back with `brightnessctl -m info`, whose fourth field is the
+    /// percentage, e.g. "intel_backlight,backlight,1200,50%,2400"
+    async fn level(&self, settings: &RotateSettings) -> Option<u32> {
+        if cfg!(target_os = "macos") {
+            let percent = (macos_brightness(settings).await? * 100.0).round() as u32;
+            log::info!("Brightness now {}%", percent);
+            return Some(percent);
+        }
+        let mut cmd = Command::new("brightnessctl");
+        cmd.arg("-m");
+        if let Some(device) = settings.brightness_device.as_deref().filter(|d| !d.is_empty()) {
+            cmd.arg("-d").arg(device);
+        }
+        cmd.arg("info");
+        let output = output(cmd, settings).await.ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let percent = text.lines().next()?.split(',').nth(3)?.strip_suffix('%')?.parse().ok()?;
+        log::info!("Brightness now {}%", percent);
+        Some(percent)
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(windows) {
+            return unsupported("Brightness");
+        }
+        if cfg!(target_os = "macos") {
+            return (!STATE.capabilities().has("brightness"))
+                .then(|| "Brightness mode needs brightness (brew install brightness); it was not found".to_string());
+        }
+        (!STATE.capabilities().has("brightnessctl"))
+            .then(|| "Brightness mode needs brightnessctl; it was not found".to_string())
+    }
+}
+
+/// `brightness`, pinned to the display id in `brightness_device` if set
+fn macos_brightness_command(settings: &RotateSettings) -> Command {
+    let mut cmd = Command::new("brightness");
+    if let Some(display) = settings.brightness_device.as_deref().filter(|d| !d.is_empty()) {
+        cmd.arg("-d").arg(display);
+    }
+    cmd
+}
+
+/// Current level from 0 to 1, from the first "display N: brightness 0.75"
+/// line of `brightness -l`
+async fn macos_brightness(settings: &RotateSettings) -> Option<f32> {
+    let mut cmd = macos_brightness_command(settings);
+    cmd.arg("-l");
+    let output = output(cmd, settings).await.ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.split_once(": brightness ")?.1.trim().parse().ok())
+}
+
+// ============================================================================
+// Monitor Brightness
+// ============================================================================
+
+/// DDC/CI brightness (VCP feature 10) of an external monitor through ddcutil.
+/// Each call takes a few hundred milliseconds, so rotations queue behind the
+/// running one and collapse into a single adjustment.
+struct MonitorBrightness;
+
+#[async_trait]
+impl ActionExecutor for MonitorBrightness {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let sign = if direction > 0 { "+" } else { "-" };
+        let value = brightness_percent(magnitude, settings).to_string();
+
+        let mut cmd = ddcutil(settings);
+        cmd.args(["setvcp", "10", sign, &value]);
+        log::info!("Monitor Brightness: ddcutil setvcp 10 {} {} (display: {:?})", sign, value, settings.display);
+        let output = output(cmd, settings).await?;
+        if !output.status.success() {
+            return Err(ddcutil_error(&output));
+        }
+        Ok(())
+    }
+
+    /// Read back with `ddcutil getvcp 10 --brief`, e.g. "VCP 10 C 50 100"
+    /// for 50 out of 100
+    async fn level(&self, settings: &RotateSettings) -> Option<u32> {
+        let mut cmd = ddcutil(settings);
+        cmd.args(["getvcp", "10", "--brief"]);
+        let output = output(cmd, settings).await.ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let fields: Vec<&str> = text.lines().find(|line| line.starts_with("VCP 10 "))?.split_whitespace().collect();
+        let current: u32 = fields.get(3)?.parse().ok()?;
+        let max: u32 = fields.get(4)?.parse().ok()?;
+        let percent = (current * 100).checked_div(max)?;
+        log::info!("Monitor brightness now {}%", percent);
+        Some(percent)
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Monitor Brightness");
+        }
+        (!STATE.capabilities().has("ddcutil"))
+            .then(|| "Monitor Brightness mode needs ddcutil; it was not found".to_string())
+    }
+}
+
+/// `ddcutil`, pinned to `display`: a number selects by ddcutil display
+/// number, anything else by serial number
+fn ddcutil(settings: &RotateSettings) -> Command {
+    let mut cmd = Command::new("ddcutil");
+    if let Some(display) = settings.display.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        if display.chars().all(|c| c.is_ascii_digit()) {
+            cmd.arg("--display").arg(display);
+        } else {
+            cmd.arg("--sn").arg(display);
+        }
+    }
+    cmd
+}
+
+/// Tell a missing or DDC-less monitor apart from other ddcutil failures.
+/// ddcutil reports both on stdout or stderr depending on the version.
+fn ddcutil_error(output: &Output) -> ActionError {
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let lower = text.to_lowercase();
+    if lower.contains("no displays found") || lower.contains("display not found") || lower.contains("does not support ddc") {
+        return ActionError::Unavailable(
+            "Monitor Brightness found no DDC/CI capable monitor; enable DDC/CI in the monitor's menu, load the i2c-dev module and check `ddcutil detect`".to_string(),
+        );
+    }
+    ActionError::exit("ddcutil", output.status.code(), text.trim().as_bytes())
+}
+
+// ============================================================================
+// Color Temperature
+// ============================================================================
+
+/// Color temperature of an unadjusted screen, which the press goes back to
+const COLOR_TEMP_NEUTRAL: u32 = 6500;
+const COLOR_TEMP_MIN: u32 = 1000;
+const COLOR_TEMP_MAX: u32 = 10000;
+
+/// How long a one-shot tool gets to exit before it is taken to be holding
+/// the gamma ramp, as gammastep and wlsunset do on Wayland
+const COLOR_TEMP_GRACE: Duration = Duration::from_millis(300);
+
+/// The tool process still holding the color temperature. Every change
+/// replaces the whole temperature, so changes run one at a time under this
+/// lock and each one ends the previous process.
+static COLOR_TEMP_HOLD: tokio::sync::Mutex<Option<tokio::process::Child>> = tokio::sync::Mutex::const_new(None);
+
+/// Nudges a color temperature target kept in `PluginState` by
+/// `color_temp_step` kelvin per step and applies it in one shot; the press
+/// goes back to 6500 K. The target is kept in the global settings across
+/// restarts.
+struct ColorTemp;
+
+#[async_trait]
+impl ActionExecutor for ColorTemp {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let mut hold = COLOR_TEMP_HOLD.lock().await;
+        let current = STATE.color_temp().unwrap_or(COLOR_TEMP_NEUTRAL);
+        let change = i64::from(delta) * i64::from(settings.color_temp_step.max(1));
+        let target = (i64::from(current) + change).clamp(i64::from(COLOR_TEMP_MIN), i64::from(COLOR_TEMP_MAX)) as u32;
+        apply_color_temp(&mut hold, target).await
+    }
+
+    async fn press(&self, _context: &CommandContext, _settings: &RotateSettings) -> Result<(), ActionError> {
+        let mut hold = COLOR_TEMP_HOLD.lock().await;
+        apply_color_temp(&mut hold, COLOR_TEMP_NEUTRAL).await
+    }
+
+    async fn title(&self, _settings: &RotateSettings) -> Option<String> {
+        Some(format!("{}K", STATE.color_temp().unwrap_or(COLOR_TEMP_NEUTRAL)))
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Color Temp");
+        }
+        color_temp_tool()
+            .is_none()
+            .then(|| "Color Temp mode needs gammastep, redshift or wlsunset; none found".to_string())
+    }
+}
+
+/// The first color temperature tool found; redshift only speaks X11
+fn color_temp_tool() -> Option<&'static str> {
+    let capabilities = STATE.capabilities();
+    if capabilities.has("gammastep") {
+        Some("gammastep")
+    } else if capabilities.has("redshift") && capabilities.x11 {
+        Some("redshift")
+    } else if capabilities.has("wlsunset") && capabilities.wayland {
+        Some("wlsunset")
+    } else {
+        None
+    }
+}
+
+/// Set the screen to `temp` kelvin, ending the process that held the last
+/// temperature, and remember it
+async fn apply_color_temp(hold: &mut Option<tokio::process::Child>, temp: u32) -> Result<(), ActionError> {
+    let Some(tool) = color_temp_tool() else {
+        return Err(ActionError::Unavailable("Color Temp mode needs gammastep, redshift or wlsunset; none found".to_string()));
+    };
+    let args = match tool {
+        // wlsunset has no one-shot mode; equal day and night temperatures
+        // would be rejected, so night is one kelvin lower
+        "wlsunset" => vec!["-t".to_string(), (temp - 1).to_string(), "-T".to_string(), temp.to_string()],
+        _ => vec!["-P".to_string(), "-O".to_string(), temp.to_string()],
+    };
+    log::info!("Color Temp: {} {}", tool, args.join(" "));
+    if crate::commands::dry_run() {
+        return Ok(());
+    }
+
+    if let Some(mut previous) = hold.take() {
+        let _ = previous.kill().await;
+    }
+    let mut cmd = Command::new(tool);
+    cmd.args(&args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = cmd.spawn().map_err(|e| ActionError::spawn(tool, e))?;
+    match tokio::time::timeout(COLOR_TEMP_GRACE, child.wait()).await {
+        Ok(Ok(status)) if status.success() => {}
+        Ok(Ok(status)) => {
+            let mut stderr = Vec::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = tokio::io::AsyncReadExt::read_to_end(&mut pipe, &mut stderr).await;
+            }
+            return Err(ActionError::exit(tool, status.code(), &stderr));
+        }
+        Ok(Err(e)) => return Err(ActionError::Spawn(e)),
+        // Still running, so it holds the temperature until replaced
+        Err(_) => *hold = Some(child),
+    }
+
+    STATE.set_color_temp(temp);
+    // The only global setting so far, so it can be written whole
+    if let Err(e) = openaction::set_global_settings(serde_json::json!({ "color_temp": temp })).await {
+        log::warn!("Failed to save the color temperature: {}", e);
+    }
+    Ok(())
+}
+
+/// Take over the temperature saved by the last run. It is applied again
+/// unless it is the neutral one, since Wayland compositors reset it when
+/// the holding process went away.
+pub(crate) async fn restore_color_temp(temp: u32) {
+    let temp = temp.clamp(COLOR_TEMP_MIN, COLOR_TEMP_MAX);
+    log::info!("Restoring color temperature {}K", temp);
+    if temp == COLOR_TEMP_NEUTRAL || color_temp_tool().is_none() || crate::commands::dry_run_enabled() {
+        STATE.set_color_temp(temp);
+        return;
+    }
+    let mut hold = COLOR_TEMP_HOLD.lock().await;
+    if let Err(e) = apply_color_temp(&mut hold, temp).await {
+        e.log("Restoring the color temperature failed");
+    }
+}
+
+/// End the process holding the color temperature, on shutdown
+pub(crate) async fn release_color_temp() {
+    if let Some(mut child) = COLOR_TEMP_HOLD.lock().await.take() {
+        let _ = child.kill().await;
+    }
+}
+
+// ============================================================================
+// Window Switch
+// ============================================================================
+
+/// Alt held down by Window Switch, with the timer that lets go of it
+struct AltHold {
+    /// Settings of the rotation that pressed Alt; the same backend releases it
+    settings: RotateSettings,
+    /// Alt was only pressed in a dry run, so there is nothing to release
+    dry_run: bool,
+    release: JoinHandle<()>,
+}
+
+/// The Alt-Tab switch in progress, whichever dial started it. An async
+/// mutex, so the keys of a rotation and of the release never interleave.
+static ALT_HOLD: tokio::sync::Mutex<Option<AltHold>> = tokio::sync::Mutex::const_new(None);
+
+/// Alt+Tab: the first rotation holds Alt, every step taps Tab (Shift+Tab
+/// counter-clockwise), and Alt is let go when the dial is pressed or has
+/// rested for `window_switch_timeout_ms`
+struct WindowSwitch;
+
+#[async_trait]
+impl ActionExecutor for WindowSwitch {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let mut hold = ALT_HOLD.lock().await;
+        let held = match hold.take() {
+            Some(held) => {
+                held.release.abort();
+                held.settings
+            }
+            None => {
+                switch_key(SwitchKey::AltDown, 1, settings).await?;
+                settings.clone()
+            }
+        };
+        let tab = if direction > 0 { SwitchKey::Tab } else { SwitchKey::ShiftTab };
+        let result = switch_key(tab, magnitude, &held).await;
+
+        let timeout = Duration::from_millis(settings.window_switch_timeout_ms);
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let mut hold = ALT_HOLD.lock().await;
+            if let Some(held) = hold.take() {
+                log::debug!("Window Switch: idle for {:?}", timeout);
+                release(held).await;
+            }
+        });
+        *hold = Some(AltHold { settings: held, dry_run: crate::commands::dry_run(), release });
+        result
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(any(windows, target_os = "macos")) {
+            return unsupported("Window Switch");
+        }
+        wheel_unavailable("Window Switch", settings)
+    }
+}
+
+/// Let go of Alt if Window Switch holds it, picking the highlighted window.
+/// Returns whether it was held. Also run on shutdown and when a device
+/// disconnects, so Alt is never left down.
+pub(crate) async fn release_alt() -> bool {
+    let mut hold = ALT_HOLD.lock().await;
+    let Some(held) = hold.take() else {
+        return false;
+    };
+    held.release.abort();
+    release(held).await;
+    true
+}
+
+async fn release(held: AltHold) {
+    if held.dry_run {
+        log::info!("Dry run, not executed: Window Switch alt up");
+        return;
+    }
+    if let Err(e) = switch_key(SwitchKey::AltUp, 1, &held.settings).await {
+        log::error!("Window Switch: releasing alt failed: {}", e);
+    }
+}
+
+/// Keys Window Switch sends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SwitchKey {
+    AltDown,
+    AltUp,
+    Tab,
+    ShiftTab,
+}
+
+impl SwitchKey {
+    /// xdotool keysym
+    fn keysym(self) -> &'static str {
+        match self {
+            SwitchKey::AltDown | SwitchKey::AltUp => "alt",
+            SwitchKey::Tab => "Tab",
+            SwitchKey::ShiftTab => "shift+Tab",
+        }
+    }
+
+    /// Linux input event codes (KEY_LEFTALT, or KEY_LEFTSHIFT and KEY_TAB),
+    /// for ydotool and the uinput device
+    pub(crate) fn codes(self) -> &'static [u16] {
+        match self {
+            SwitchKey::AltDown | SwitchKey::AltUp => &[56],
+            SwitchKey::Tab => &[15],
+            SwitchKey::ShiftTab => &[42, 15],
+        }
+    }
+}
+
+/// Send `key` through the configured input backend: Alt goes down or up
+/// once, Tab is tapped `repeat` times, up to `MAX_KEY_REPEAT`
+async fn switch_key(key: SwitchKey, repeat: u32, settings: &RotateSettings) -> Result<(), ActionError> {
+    let repeat = repeat.min(MAX_KEY_REPEAT);
+    #[cfg(all(feature = "uinput", target_os = "linux"))]
+    if settings.scroll_backend == ScrollBackend::Uinput {
+        log::info!("Window Switch: uinput {:?} x{}", key, repeat);
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        if let Some(result) = crate::uinput::switch_key(key, repeat).await {
+            return result;
+        }
+    }
+    let cmd = match settings.scroll_backend.tool() {
+        ScrollBackend::Auto | ScrollBackend::Xdotool | ScrollBackend::Uinput | ScrollBackend::SendInput | ScrollBackend::Quartz => match key {
+            SwitchKey::AltDown => "xdotool keydown alt".to_string(),
+            SwitchKey::AltUp => "xdotool keyup alt".to_string(),
+            // No --clearmodifiers, which would lift the held Alt
+            SwitchKey::Tab | SwitchKey::ShiftTab => format!("xdotool key --repeat {} {}", repeat, key.keysym()),
+        },
+        ScrollBackend::Ydotool => {
+            let codes = key.codes();
+            let down = codes.iter().map(|code| format!("{}:1", code)).collect::<Vec<_>>().join(" ");
+            let up = codes.iter().rev().map(|code| format!("{}:0", code)).collect::<Vec<_>>().join(" ");
+            let events = match key {
+                SwitchKey::AltDown => down,
+                SwitchKey::AltUp => up,
+                SwitchKey::Tab | SwitchKey::ShiftTab => vec![format!("{} {}", down, up); repeat as usize].join(" "),
+            };
+            format!("ydotool key {}", events)
+        }
+    };
+    log::info!("Window Switch: {}", cmd);
+
+    run(shell(&cmd), settings).await
+}
+
+// ============================================================================
+// Tab Switch
+// ============================================================================
+
+/// A key combination for the input backends, modifiers first
+#[derive(Clone, Copy)]
+pub(crate) struct Chord {
+    /// xdotool key spec
+    keysym: &'static str,
+    /// Linux input event codes, for ydotool and the uinput device; SendInput
+    /// maps them to virtual keys
+    codes: &'static [u16],
+}
+
+impl TabKeys {
+    /// Chords for the next tab, the previous tab and closing the tab
+    fn chords(self) -> [Chord; 3] {
+        const CTRL_W: Chord = Chord { keysym: "ctrl+w", codes: &[29, 17] };
+        match self {
+            TabKeys::Tab => [
+                Chord { keysym: "ctrl+Tab", codes: &[29, 15] },
+                Chord { keysym: "ctrl+shift+Tab", codes: &[29, 42, 15] },
+                CTRL_W,
+            ],
+            TabKeys::Page => [
+                Chord { keysym: "ctrl+Next", codes: &[29, 109] },
+                Chord { keysym: "ctrl+Prior", codes: &[29, 104] },
+                CTRL_W,
+            ],
+            TabKeys::Arrow => [
+                Chord { keysym: "ctrl+shift+Right", codes: &[29, 42, 106] },
+                Chord { keysym: "ctrl+shift+Left", codes: &[29, 42, 105] },
+                Chord { keysym: "ctrl+shift+w", codes: &[29, 42, 17] },
+            ],
+        }
+    }
+}
+
+/// Next or previous tab, one shortcut per step; closes the tab on press
+struct TabSwitch;
+
+#[async_trait]
+impl ActionExecutor for TabSwitch {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let [next, previous, _] = settings.tab_keys.chords();
+        send_chord("Tab Switch", if direction > 0 { next } else { previous }, magnitude, settings).await
+    }
+
+    /// Close the current tab
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let [_, _, close] = settings.tab_keys.chords();
+        send_chord("Tab Switch", close, 1, settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(target_os = "macos") {
+            return unsupported("Tab Switch");
+        }
+        wheel_unavailable("Tab Switch", settings)
+    }
+}
+
+/// Press `chord` `repeat` times through the configured input backend
+async fn send_chord(name: &str, chord: Chord, repeat: u32, settings: &RotateSettings) -> Result<(), ActionError> {
+    let chord = KeyChord { keysym: chord.keysym.to_string(), codes: Some(chord.codes.to_vec()) };
+    send_keys(name, &[chord], repeat, settings).await
+}
+
+/// Press `chords` in order, `repeat` times up to `MAX_KEY_REPEAT`, through
+/// the configured input backend
+async fn send_keys(name: &str, chords: &[KeyChord], repeat: u32, settings: &RotateSettings) -> Result<(), ActionError> {
+    let repeat = repeat.min(MAX_KEY_REPEAT);
+    let keysyms: Vec<&str> = chords.iter().map(|chord| chord.keysym.as_str()).collect();
+    #[cfg(all(feature = "uinput", target_os = "linux"))]
+    if settings.scroll_backend == ScrollBackend::Uinput {
+        log::info!("{}: uinput {} x{}", name, keysyms.join(" "), repeat);
+        let codes = chords.iter().map(|chord| chord.codes("uinput")).collect::<Result<Vec<_>, _>>()?;
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        if let Some(result) = crate::uinput::chords(&codes, repeat).await {
+            return result;
+        }
+    }
+    #[cfg(windows)]
+    if settings.scroll_backend.tool() == ScrollBackend::SendInput {
+        log::info!("{}: SendInput {} x{}", name, keysyms.join(" "), repeat);
+        let codes = chords.iter().map(|chord| chord.codes("SendInput")).collect::<Result<Vec<_>, _>>()?;
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        return crate::win32::chords(&codes, repeat);
+    }
+    // Arguments rather than a shell line, as key specs come from settings
+    let (program, args) = match settings.scroll_backend.tool() {
+        ScrollBackend::Auto | ScrollBackend::Xdotool | ScrollBackend::Uinput | ScrollBackend::SendInput | ScrollBackend::Quartz => {
+            let mut args = vec!["key".to_string(), "--clearmodifiers".to_string(), "--repeat".to_string(), repeat.to_string()];
+            args.extend(keysyms.iter().map(|keysym| keysym.to_string()));
+            ("xdotool", args)
+        }
+        ScrollBackend::Ydotool => {
+            let mut events = Vec::new();
+            for chord in chords {
+                let codes = chord.codes("ydotool")?;
+                events.extend(codes.iter().map(|code| format!("{}:1", code)));
+                events.extend(codes.iter().rev().map(|code| format!("{}:0", code)));
+            }
+            let mut args = vec!["key".to_string()];
+            for _ in 0..repeat {
+                args.extend(events.iter().cloned());
+            }
+            ("ydotool", args)
+        }
+    };
+    log::info!("{}: {} {}", name, program, args.join(" "));
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    run(cmd, settings).await
+}
+
+// ============================================================================
+// Undo / Redo
+// ============================================================================
+
+const CTRL_Z: Chord = Chord { keysym: "ctrl+z", codes: &[29, 44] };
+const CTRL_S: Chord = Chord { keysym: "ctrl+s", codes: &[29, 31] };
+
+impl RedoKeys {
+    fn chord(self) -> Chord {
+        match self {
+            RedoKeys::ShiftZ => Chord { keysym: "ctrl+shift+z", codes: &[29, 42, 44] },
+            RedoKeys::Y => Chord { keysym: "ctrl+y", codes: &[29, 21] },
+        }
+    }
+}
+
+/// Undo counter-clockwise and redo clockwise, one shortcut per tick; saves
+/// on press
+struct UndoRedo;
+
+#[async_trait]
+impl ActionExecutor for UndoRedo {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let chord = if direction > 0 { settings.redo_keys.chord() } else { CTRL_Z };
+        send_chord("Undo/Redo", chord, magnitude, settings).await
+    }
+
+    /// Save with Ctrl+S
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        send_chord("Undo/Redo", CTRL_S, 1, settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(target_os = "macos") {
+            return unsupported("Undo/Redo");
+        }
+        wheel_unavailable("Undo/Redo", settings)
+    }
+}
+
+// ============================================================================
+// Pan and Keys
+// ============================================================================
+
+const RIGHT: Chord = Chord { keysym: "Right", codes: &[106] };
+const LEFT: Chord = Chord { keysym: "Left", codes: &[105] };
+
+/// Right arrow clockwise and Left counter-clockwise, for timelines and
+/// slides. A `cw_keys` or `ccw_keys` spec replaces the arrow for its
+/// direction, so Pan doubles as a preset of Keys mode.
+struct Pan;
+
+#[async_trait]
+impl ActionExecutor for Pan {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let (spec, arrow) = if direction > 0 { (&settings.cw_keys, RIGHT) } else { (&settings.ccw_keys, LEFT) };
+        if spec.trim().is_empty() {
+            return send_chord("Pan", arrow, magnitude, settings).await;
+        }
+        send_keys("Pan", &keyspec::parse(spec)?, magnitude, settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(target_os = "macos") {
+            return unsupported("Pan");
+        }
+        wheel_unavailable("Pan", settings)
+    }
+}
+
+/// Send the `cw_keys` or `ccw_keys` spec once per step; `press_keys` on press
+struct Keys;
+
+#[async_trait]
+impl ActionExecutor for Keys {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        let spec = if direction > 0 { &settings.cw_keys } else { &settings.ccw_keys };
+        send_keys("Keys", &keyspec::parse(spec)?, magnitude, settings).await
+    }
+
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        send_keys("Keys", &keyspec::parse(&settings.press_keys)?, 1, settings).await
+    }
+
+    fn unavailable(&self, settings: &RotateSettings) -> Option<String> {
+        if cfg!(target_os = "macos") {
+            return unsupported("Keys");
+        }
+        wheel_unavailable("Keys", settings)
+    }
+}
+
+// ============================================================================
+// OBS
+// ============================================================================
+
+/// OBS Studio over obs-websocket: the volume of `obs_input` in dB, or the
+/// program scene. Pressing runs the studio mode transition.
+struct Obs;
+
+#[async_trait]
+impl ActionExecutor for Obs {
+    #[cfg_attr(not(feature = "obs"), allow(unused_variables))]
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "obs")]
+        {
+            let server = settings.obs_server();
+            match settings.obs_target {
+                crate::ObsTarget::InputVolume => {
+                    let input = obs_input(settings)?;
+                    let db = crate::obs::input_volume(&server, input).await?;
+                    let step = f64::from(settings.obs_volume_step.clamp(0.1, 20.0)) * f64::from(delta);
+                    let target = ((db + step) * 10.0).round() / 10.0;
+                    let target = target.clamp(crate::obs::MIN_VOLUME_DB, crate::obs::MAX_VOLUME_DB);
+                    log::info!("OBS: {} volume {:.1} dB -> {:.1} dB", input, db, target);
+                    if crate::commands::dry_run() {
+                        return Ok(());
+                    }
+                    return crate::obs::set_input_volume(&server, input, target).await;
+                }
+                crate::ObsTarget::Scene => {
+                    let (current, scenes) = crate::obs::scenes(&server).await?;
+                    if scenes.is_empty() {
+                        return Err(ActionError::Obs("there are no scenes".to_string()));
+                    }
+                    let len = scenes.len() as i64;
+                    let index = match scenes.iter().position(|scene| *scene == current) {
+                        Some(index) => (index as i64 + i64::from(delta)).rem_euclid(len),
+                        None => 0,
+                    };
+                    let scene = &scenes[index as usize];
+                    log::info!("OBS: scene {} -> {}", current, scene);
+                    if crate::commands::dry_run() {
+                        return Ok(());
+                    }
+                    return crate::obs::set_scene(&server, scene).await;
+                }
+            }
+        }
+        #[allow(unreachable_code)]
+        Err(ActionError::Unavailable(obs_unbuilt()))
+    }
+
+    /// Studio mode transition, preview to program
+    #[cfg_attr(not(feature = "obs"), allow(unused_variables))]
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "obs")]
+        {
+            log::info!("OBS: transition");
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            return crate::obs::transition(&settings.obs_server()).await;
+        }
+        #[allow(unreachable_code)]
+        Err(ActionError::Unavailable(obs_unbuilt()))
+    }
+
+    /// The input's volume or the scene now live
+    #[cfg_attr(not(feature = "obs"), allow(unused_variables))]
+    async fn title(&self, settings: &RotateSettings) -> Option<String> {
+        #[cfg(feature = "obs")]
+        {
+            let server = settings.obs_server();
+            return match settings.obs_target {
+                crate::ObsTarget::InputVolume => {
+                    let db = crate::obs::input_volume(&server, obs_input(settings).ok()?).await.ok()?;
+                    Some(format!("{:.1} dB", db))
+                }
+                crate::ObsTarget::Scene => crate::obs::scenes(&server).await.ok().map(|(current, _)| current),
+            };
+        }
+        #[allow(unreachable_code)]
+        None
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        (!cfg!(feature = "obs")).then(obs_unbuilt)
+    }
+}
+
+#[cfg(feature = "obs")]
+fn obs_input(settings: &RotateSettings) -> Result<&str, ActionError> {
+    let input = settings.obs_input.trim();
+    if input.is_empty() {
+        return Err(ActionError::Obs("set obs_input to the name of an audio source".to_string()));
+    }
+    Ok(input)
+}
+
+fn obs_unbuilt() -> String {
+    "OBS mode needs the plugin built with the obs feature".to_string()
+}
+
+// ============================================================================
+// MPD
+// ============================================================================
+
+/// MPD over its own protocol, no mpc needed: the volume by `volume_step` or
+/// the position by `seek_seconds`, per `mpd_target`. Play/pause presses on
+/// a dial in this mode go to MPD.
+struct Mpd;
+
+#[async_trait]
+impl ActionExecutor for Mpd {
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let server = settings.mpd_server();
+        let command = match settings.mpd_target {
+            MpdTarget::Volume => {
+                let volume = mpd_volume(&server).await?;
+                let target = (i64::from(volume) + i64::from(settings.volume_step()) * i64::from(delta)).clamp(0, 100);
+                format!("setvol {}", target)
+            }
+            MpdTarget::Seek => {
+                let seconds = i64::from(settings.seek_seconds.clamp(1, 600)) * i64::from(delta);
+                format!("seekcur {:+}", seconds)
+            }
+        };
+        log::info!("MPD: {}", command);
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        crate::mpd::command(&server, &command).await.map(|_| ())
+    }
+
+    /// Pause while playing, otherwise play
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let server = settings.mpd_server();
+        let status = crate::mpd::status(&server).await?;
+        let command = match crate::mpd::field(&status, "state") {
+            Some("play") => "pause 1",
+            Some("pause") => "pause 0",
+            _ => "play",
+        };
+        log::info!("MPD: {}", command);
+        if crate::commands::dry_run() {
+            return Ok(());
+        }
+        crate::mpd::command(&server, command).await.map(|_| ())
+    }
+
+    async fn level(&self, settings: &RotateSettings) -> Option<u32> {
+        if settings.mpd_target != MpdTarget::Volume {
+            return None;
+        }
+        mpd_volume(&settings.mpd_server()).await.ok()
+    }
+
+    /// Position in the song after a seek
+    async fn title(&self, settings: &RotateSettings) -> Option<String> {
+        if settings.mpd_target != MpdTarget::Seek {
+            return None;
+        }
+        let status = crate::mpd::status(&settings.mpd_server()).await.ok()?;
+        let elapsed = crate::mpd::field(&status, "elapsed")?.parse::<f64>().ok()? as u64;
+        Some(format!("{}:{:02}", elapsed / 60, elapsed % 60))
+    }
+}
+
+/// MPD's volume in percent; status reports -1 (or nothing) without a mixer
+async fn mpd_volume(server: &crate::mpd::Server) -> Result<u32, ActionError> {
+    let status = crate::mpd::status(server).await?;
+    crate::mpd::field(&status, "volume")
+        .and_then(|volume| volume.parse::<u32>().ok())
+        .ok_or_else(|| ActionError::Mpd("no mixer to change the volume of".to_string()))
+}
+
+// ============================================================================
+// Home Assistant
+// ============================================================================
+
+/// Dims the Home Assistant light `ha_entity` by `brightness_step` percent
+/// per tick through light.turn_on; pressing toggles it
+struct HomeAssistant;
+
+#[async_trait]
+impl ActionExecutor for HomeAssistant {
+    #[cfg_attr(not(feature = "home-assistant"), allow(unused_variables))]
+    async fn rotate(&self, delta: i32, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "home-assistant")]
+        {
+            let entity = ha_entity(settings)?;
+            let step = (i64::from(settings.brightness_step) * i64::from(delta)).clamp(-100, 100);
+            log::info!("Home Assistant: {} brightness {:+}%", entity, step);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            let data = serde_json::json!({ "entity_id": entity, "brightness_step_pct": step });
+            return crate::home_assistant::call_service(&settings.ha_server(), "light", "turn_on", data).await;
+        }
+        #[allow(unreachable_code)]
+        Err(ActionError::Unavailable(ha_unbuilt()))
+    }
+
+    /// Toggle the entity, in whichever domain it is
+    #[cfg_attr(not(feature = "home-assistant"), allow(unused_variables))]
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "home-assistant")]
+        {
+            let entity = ha_entity(settings)?;
+            let domain = entity.split('.').next().unwrap_or("homeassistant");
+            log::info!("Home Assistant: toggle {}", entity);
+            if crate::commands::dry_run() {
+                return Ok(());
+            }
+            let data = serde_json::json!({ "entity_id": entity });
+            return crate::home_assistant::call_service(&settings.ha_server(), domain, "toggle", data).await;
+        }
+        #[allow(unreachable_code)]
+        Err(ActionError::Unavailable(ha_unbuilt()))
+    }
+
+    /// Brightness in percent; Home Assistant reports 0-255, and nothing
+    /// while the light is off
+    #[cfg_attr(not(feature = "home-assistant"), allow(unused_variables))]
+    async fn level(&self, settings: &RotateSettings) -> Option<u32> {
+        #[cfg(feature = "home-assistant")]
+        {
+            let state = crate::home_assistant::state(&settings.ha_server(), ha_entity(settings).ok()?).await.ok()?;
+            let brightness = state["attributes"]["brightness"].as_u64().unwrap_or(0);
+            return Some(((brightness * 100 + 127) / 255) as u32);
+        }
+        #[allow(unreachable_code)]
+        None
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        (!cfg!(feature = "home-assistant")).then(ha_unbuilt)
+    }
+}
+
+#[cfg(feature = "home-assistant")]
+fn ha_entity(settings: &RotateSettings) -> Result<&str, ActionError> {
+    let entity = settings.ha_entity.trim();
+    if !entity.contains('.') {
+        return Err(ActionError::HomeAssistant("set ha_entity to an entity id, e.g. light.desk".to_string()));
+    }
+    Ok(entity)
+}
+
+fn ha_unbuilt() -> String {
+    "Home Assistant mode needs the plugin built with the home-assistant feature".to_string()
+}
+
+// ============================================================================
+// Webhook
+// ============================================================================
+
+/// One HTTP request per coalesced rotation to `webhook_url`, with the Custom
+/// placeholders filled in the URL and body; `{steps}` is the net movement
+struct Webhook;
+
+#[async_trait]
+impl ActionExecutor for Webhook {
+    #[cfg_attr(not(feature = "webhook"), allow(unused_variables))]
+    async fn rotate(&self, delta: i32, context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "webhook")]
+        {
+            let (direction, magnitude) = split(delta);
+            let url = expand_template(&settings.webhook_url, direction, magnitude, &context.device_id);
+            let body = expand_template(&settings.webhook_body, direction, magnitude, &context.device_id);
+            return send_webhook(url, body, settings).await;
+        }
+        #[allow(unreachable_code)]
+        Err(ActionError::Unavailable(webhook_unbuilt()))
+    }
+
+    #[cfg_attr(not(feature = "webhook"), allow(unused_variables))]
+    async fn press(&self, _context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        #[cfg(feature = "webhook")]
+        return send_webhook(settings.webhook_press_url.clone(), settings.webhook_press_body.clone(), settings).await;
+        #[allow(unreachable_code)]
+        Err(ActionError::Unavailable(webhook_unbuilt()))
+    }
+
+    fn unavailable(&self, _settings: &RotateSettings) -> Option<String> {
+        (!cfg!(feature = "webhook")).then(webhook_unbuilt)
+    }
+}
+
+/// Send `body` to `url` with the dial's method and headers, timing out with
+/// the command timeout
+#[cfg(feature = "webhook")]
+async fn send_webhook(url: String, body: String, settings: &RotateSettings) -> Result<(), ActionError> {
+    log::info!("Webhook: {} {}", settings.webhook_method, crate::webhook::redacted(&url));
+    if crate::commands::dry_run() {
+        return Ok(());
+    }
+    let request = crate::webhook::Request {
+        method: settings.webhook_method.clone(),
+        url,
+        headers: settings.webhook_headers.clone(),
+        body,
+        timeout: settings.command_timeout(),
+    };
+    crate::webhook::send(&request).await
+}
+
+fn webhook_unbuilt() -> String {
+    "Webhook mode needs the plugin built with the webhook feature".to_string()
+}
+
+// ============================================================================
+// Custom
+// ============================================================================
+
+/// User commands: the `command` template if set, otherwise the per-direction
+/// commands, once per step unless they use `{dir}` or `{mag}`. Always
+/// assumed to be available.
+struct Custom;
+
+#[async_trait]
+impl ActionExecutor for Custom {
+    async fn rotate(&self, delta: i32, context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+        let (direction, magnitude) = split(delta);
+        if !settings.command.trim().is_empty() {
+            return execute_template(&settings.command, direction, magnitude, context, settings).await;
+        }
+        let cmd = if direction > 0 { &settings.cw_command } else { &settings.ccw_command };
+        if cmd.trim().is_empty() {
+            return Err(ActionError::EmptyCommand);
+        }
+        // Only expand commands written for it, so existing ones with literal
+        // braces (awk, shell groups) run as before
+        if cmd.contains("{dir}") || cmd.contains("{mag}") {
+            return execute_template(cmd, direction, magnitude, context, settings).await;
+        }
+        for _ in 0..magnitude {
+            log::info!("Custom: {}", cmd);
+            run(custom_command(cmd, context, direction, 1, settings)?, settings).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Run a command template once for the whole rotation when it uses
+/// `{steps}` or `{mag}`, otherwise once per step
+async fn execute_template(template: &str, direction: i8, magnitude: u32, context: &CommandContext, settings: &RotateSettings) -> Result<(), ActionError> {
+    let (runs, steps) = if template.contains("{steps}") || template.contains("{mag}") { (1, magnitude) } else { (magnitude, 1) };
+    let cmd = expand_template(template, direction, steps, &shell_quote(&context.device_id));
+    log::info!("Custom: {}", cmd);
+    for _ in 0..runs {
+        run(custom_command(&cmd, context, direction, steps, settings)?, settings).await?;
+    }
+    Ok(())
+}
+
+/// Substitute `{direction}`, `{sign}`, `{steps}` and `{device}` in a command
+/// template, plus the short forms `{dir}` (`+1`/`-1`) and `{mag}` (the same
+/// as `{steps}`, after acceleration). `device` is inserted as given, so
+/// callers building commands pass it shell-quoted; unknown placeholders are
+/// kept.
+///
+/// One script can then serve both directions: `cw_command` and
+/// `ccw_command` both set to `~/bin/dial.sh {dir} {mag}` run
+/// `~/bin/dial.sh +1 3` for three fast clockwise ticks and
+/// `~/bin/dial.sh -1 1` for one counter-clockwise tick.
+fn expand_template(template: &str, direction: i8, steps: u32, device: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let placeholder = &rest[start..=start + len];
+        match &placeholder[1..len] {
+            "direction" => out.push_str(if direction > 0 { "1" } else { "-1" }),
+            "sign" => out.push_str(if direction > 0 { "+" } else { "-" }),
+            "dir" => out.push_str(if direction > 0 { "+1" } else { "-1" }),
+            "steps" | "mag" => out.push_str(&steps.to_string()),
+            "device" => out.push_str(device),
+            name => {
+                log::warn!("Unknown placeholder {{{}}} in template", name);
+                out.push_str(placeholder);
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wpctl_volume() {
+        assert_eq!(parse_wpctl_volume("Volume: 0.45\n"), Some((45, false)));
+        assert_eq!(parse_wpctl_volume("Volume: 0.30 [MUTED]\n"), Some((30, true)));
+        assert_eq!(parse_wpctl_volume("Volume: 1.50\n"), Some((150, false)));
+        assert_eq!(parse_wpctl_volume("Translate ID error: ID 0 not found\n"), None);
+    }
+
+    #[test]
+    fn pactl_volume() {
+        let stereo = "Volume: front-left: 42598 /  65% / -11.23 dB,   front-right: 45875 /  70% / -9.30 dB\n        balance 0.08\n";
+        assert_eq!(parse_pactl_volume(stereo), Some(65));
+        assert_eq!(parse_pactl_volume("Volume: mono: 65536 / 100% / 0.00 dB\n"), Some(100));
+        let surround = "Volume: front-left: 19661 /  30% / -31.37 dB,   front-right: 19661 /  30% / -31.37 dB,   \
+                        rear-left: 19661 /  30% / -31.37 dB,   rear-right: 19661 /  30% / -31.37 dB\n        balance 0.00\n";
+        assert_eq!(parse_pactl_volume(surround), Some(30));
+        assert_eq!(parse_pactl_volume("Failed to get sink volume: No such entity\n"), None);
+    }
+
+    #[test]
+    fn amixer_volume() {
+        let master = "Simple mixer control 'Master',0
+  Capabilities: pvolume pswitch pswitch-joined
+  Playback channels: Front Left - Front Right
+  Limits: Playback 0 - 65536
+  Mono:
+  Front Left: Playback 45000 [69%] [on]
+  Front Right: Playback 45000 [69%] [on]
+";
+        assert_eq!(parse_amixer_volume(master), Some((69, false)));
+        let muted = "Simple mixer control 'Master',0
+  Capabilities: pvolume pvolume-joined pswitch pswitch-joined
+  Playback channels: Mono
+  Limits: Playback 0 - 87
+  Mono: Playback 0 [0%] [-65.25dB] [off]
+";
+        assert_eq!(parse_amixer_volume(muted), Some((0, true)));
+        // Capture elements without a switch report no [on]/[off]
+        let capture = "Simple mixer control 'Capture',0
+  Capabilities: cvolume
+  Capture channels: Front Left - Front Right
+  Limits: Capture 0 - 63
+  Front Left: Capture 40 [63%] [12.00dB]
+  Front Right: Capture 40 [63%] [12.00dB]
+";
+        assert_eq!(parse_amixer_volume(capture), Some((63, false)));
+        assert_eq!(parse_amixer_volume("amixer: Unable to find simple control 'Master',0\n"), None);
+    }
+
+    #[test]
+    fn volume_commands() {
+        let settings = RotateSettings { volume_step: 5, ..RotateSettings::default() };
+        let step = AudioTarget::Output.step(1, &settings);
+        let amixer = |direction| volume_command(AudioTarget::Output, &VolumeBackend::Amixer, "Master", direction, step);
+        assert_eq!(amixer(1), "amixer sset 'Master' 5%+");
+        assert_eq!(amixer(-1), "amixer sset 'Master' 5%-");
+        let wpctl = |direction| volume_command(AudioTarget::Output, &VolumeBackend::Wpctl, "Master", direction, step);
+        assert_eq!(wpctl(1), "wpctl set-volume @DEFAULT_AUDIO_SINK@ 5%+");
+        assert_eq!(wpctl(-1), "wpctl set-volume @DEFAULT_AUDIO_SINK@ 5%-");
+        let pactl = |direction| volume_command(AudioTarget::Input, &VolumeBackend::Pactl, "Capture", direction, step);
+        assert_eq!(pactl(1), "pactl set-source-volume @DEFAULT_SOURCE@ +5%");
+        assert_eq!(pactl(-1), "pactl set-source-volume @DEFAULT_SOURCE@ -5%");
+    }
+
+    #[test]
+    fn scroll_clicks_saturate() {
+        let settings = RotateSettings { scroll_lines: 3, hscroll_columns: 2, ..RotateSettings::default() };
+        assert_eq!(Axis::Vertical.clicks(4, &settings), 12);
+        assert_eq!(Axis::Horizontal.clicks(5, &settings), 10);
+        // Each axis saturates on its own setting
+        let settings = RotateSettings { scroll_lines: u32::MAX / 2, ..settings };
+        assert_eq!(Axis::Vertical.clicks(3, &settings), u32::MAX);
+        assert_eq!(Axis::Horizontal.clicks(3, &settings), 6);
+        let settings = RotateSettings { scroll_lines: 1, hscroll_columns: u32::MAX, ..settings };
+        assert_eq!(Axis::Horizontal.clicks(2, &settings), u32::MAX);
+        assert_eq!(Axis::Vertical.clicks(2, &settings), 2);
+    }
+
+    #[test]
+    fn unsupported_modes_follow_the_platform() {
+        use ActionMode::*;
+        let settings = RotateSettings::default();
+        let unsupported: &[ActionMode] = if cfg!(windows) {
+            &[SinkSwitch, Workspace, MediaSeek, PlaybackRate, Brightness, MonitorBrightness, ColorTemp, WindowSwitch]
+        } else if cfg!(target_os = "macos") {
+            &[SinkSwitch, Workspace, PlaybackRate, MouseMove, Zoom, MonitorBrightness, ColorTemp, WindowSwitch, TabSwitch, UndoRedo, Pan, Keys]
+        } else {
+            &[]
+        };
+        // Player Select depends on finding playerctl, so it is left out
+        for (mode, executor) in EXECUTORS.iter().filter(|(mode, _)| **mode != PlayerSelect) {
+            let reason = executor.unavailable(&settings).unwrap_or_default();
+            assert_eq!(reason.ends_with("unsupported on this platform"), unsupported.contains(mode), "{:?}: {:?}", mode, reason);
+        }
+        // The native wheel injectors need no tools
+        if cfg!(any(windows, target_os = "macos")) {
+            assert_eq!(executor(&Scroll).unavailable(&settings), None);
+            assert_eq!(executor(&HScroll).unavailable(&settings), None);
+        }
+    }
+
+    #[test]
+    fn template_placeholders() {
+        let template = "dial {direction} {sign} {steps} {device}";
+        assert_eq!(expand_template(template, 1, 3, "'n1'"), "dial 1 + 3 'n1'");
+        assert_eq!(expand_template(template, -1, 1, "'n1'"), "dial -1 - 1 'n1'");
+        assert_eq!(expand_template("no placeholders", 1, 1, ""), "no placeholders");
+    }
+
+    #[test]
+    fn unknown_placeholder_is_kept() {
+        assert_eq!(expand_template("awk '{print $1}' {steps}", 1, 2, ""), "awk '{print $1}' 2");
+        assert_eq!(expand_template("{volume} {sign}", 1, 1, ""), "{volume} +");
+    }
+
+    #[test]
+    fn unclosed_brace_is_kept() {
+        assert_eq!(expand_template("echo {steps} {sign", -1, 4, ""), "echo 4 {sign");
+        assert_eq!(expand_template("{", 1, 1, ""), "{");
+    }
+
+    #[test]
+    fn short_placeholders() {
+        assert_eq!(expand_template("~/bin/dial.sh {dir} {mag}", 1, 3, ""), "~/bin/dial.sh +1 3");
+        assert_eq!(expand_template("~/bin/dial.sh {dir} {mag}", -1, 1, ""), "~/bin/dial.sh -1 1");
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn short_placeholders_run_once_per_rotation() {
+        let out = std::env::temp_dir().join(format!("n1-dir-mag-test-{}", std::process::id()));
+        let command = format!("echo {{dir}} {{mag}} >> {}", out.display());
+        let settings = RotateSettings {
+            mode: Some(ActionMode::Custom),
+            cw_command: command.clone(),
+            ccw_command: command,
+            ..RotateSettings::default()
+        };
+        let context = CommandContext { device_id: "n1".to_string(), pressed: false };
+        let cw = Custom.rotate(3, &context, &settings).await;
+        let ccw = Custom.rotate(-1, &context, &settings).await;
+        let written = std::fs::read_to_string(&out).unwrap_or_default();
+        let _ = std::fs::remove_file(&out);
+        assert!(cw.is_ok() && ccw.is_ok());
+        assert_eq!(written, "+1 3\n-1 1\n");
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn device_is_shell_quoted() {
+        let out = std::env::temp_dir().join(format!("n1-device-test-{}", std::process::id()));
+        let device = "n1 $(echo x)'; echo y";
+        let context = CommandContext { device_id: device.to_string(), pressed: false };
+        let settings = RotateSettings::default();
+        let template = format!("printf %s {{device}} > {}", out.display());
+        let result = execute_template(&template, 1, 1, &context, &settings).await;
+        let written = std::fs::read_to_string(&out).unwrap_or_default();
+        let _ = std::fs::remove_file(&out);
+        assert!(result.is_ok());
+        assert_eq!(written, device);
+    }
+
+    #[test]
+    fn volume_steps_saturate() {
+        let settings = RotateSettings { volume_step: 5, mic_volume_step: 2, ..RotateSettings::default() };
+        assert_eq!(AudioTarget::Output.step(3, &settings), 15);
+        assert_eq!(AudioTarget::Input.step(3, &settings), 6);
+        assert_eq!(AudioTarget::Output.step(u32::MAX, &settings), u32::MAX);
+    }
+
+    #[test]
+    fn brightness_steps_stop_at_100() {
+        let settings = RotateSettings { brightness_step: 10, ..RotateSettings::default() };
+        assert_eq!(brightness_percent(3, &settings), 30);
+        assert_eq!(brightness_percent(11, &settings), 100);
+        assert_eq!(brightness_percent(u32::MAX, &settings), 100);
+        let settings = RotateSettings { brightness_step: 0, ..settings };
+        assert_eq!(brightness_percent(1, &settings), 1);
+    }
+
+    #[test]
+    fn sinks() {
+        let list = "Sink #0
+\tState: SUSPENDED
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo
+\tDescription: Built-in Audio Analog Stereo
+\tDriver: PipeWire
+\tSample Specification: s32le 2ch 48000Hz
+\tOwner Module: 4294967295
+\tMute: no
+\tProperties:
+\t\talsa.name = \"ALC257 Analog\"
+\t\tdevice.description = \"Built-in Audio Analog Stereo\"
+\t\tnode.name = \"alsa_output.pci-0000_00_1f.3.analog-stereo\"
+\tPorts:
+\t\tanalog-output-speaker: Speakers (type: Speaker, priority: 100, availability unknown)
+\tActive Port: analog-output-speaker
+
+Sink #55
+\tState: RUNNING
+\tName: bluez_output.AC_80_0A_2F_42_8A.1
+\tDescription: WH-1000XM4
+\tDriver: PipeWire
+\tMonitor Source: bluez_output.AC_80_0A_2F_42_8A.1.monitor
+";
+        let sinks = parse_sinks(list);
+        let names: Vec<(&str, &str)> = sinks.iter().map(|sink| (sink.name.as_str(), sink.description.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("alsa_output.pci-0000_00_1f.3.analog-stereo", "Built-in Audio Analog Stereo"),
+                ("bluez_output.AC_80_0A_2F_42_8A.1", "WH-1000XM4"),
+            ]
+        );
+        assert!(parse_sinks("").is_empty());
+    }
+}
+
@@ -0,0 +1,200 @@
+/*!
+ * MPD over its native protocol
+ *
+ * Like the OBS client, one connection is opened on first use and kept; MPD
+ * drops idle clients after its connection_timeout (60 s by default), so a
+ * request on a closed connection is retried once on a fresh one.
+ *
+ * Replies are "key: value" lines ending in "OK", or a single
+ * "ACK [error@command_listNum] {command} message" line.
+ */
+
+use crate::error::ActionError;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// How long connecting, or one command, may take
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where MPD listens, from the dial's settings
+#[derive(Clone, PartialEq)]
+pub(crate) struct Server {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+}
+
+struct Connection {
+    server: Server,
+    stream: BufReader<TcpStream>,
+}
+
+static CONNECTION: Mutex<Option<Connection>> = Mutex::const_new(None);
+
+/// Run one command and return the key/value pairs of its reply
+pub(crate) async fn command(server: &Server, command: &str) -> Result<Vec<(String, String)>, ActionError> {
+    let mut connection = CONNECTION.lock().await;
+    // A kept connection may have been closed by MPD since its last use
+    let mut retry = connection.as_ref().is_some_and(|conn| conn.server == *server);
+    loop {
+        if connection.as_ref().is_none_or(|conn| conn.server != *server) {
+            *connection = None;
+            *connection = Some(connect(server).await?);
+        }
+        let conn = connection.as_mut().expect("connected above");
+        match tokio::time::timeout(TIMEOUT, conn.call(command)).await {
+            Ok(Ok(reply)) => return reply,
+            Ok(Err(e)) => {
+                *connection = None;
+                if !retry {
+                    return Err(ActionError::Mpd(format!("{}: connection lost: {}", command, e)));
+                }
+                log::debug!("MPD: connection lost ({}), reconnecting", e);
+                retry = false;
+            }
+            Err(_) => {
+                *connection = None;
+                return Err(ActionError::Mpd(format!("{}: no reply within {:?}", command, TIMEOUT)));
+            }
+        }
+    }
+}
+
+async fn connect(server: &Server) -> Result<Connection, ActionError> {
+    let address = format!("{}:{}", server.host, server.port);
+    let handshake = async {
+        let stream = TcpStream::connect(&address).await.map_err(|e| {
+            log::debug!("MPD: connecting to {}: {}", address, e);
+            ActionError::Unavailable(format!("MPD is not running at {}", address))
+        })?;
+        let mut stream = BufReader::new(stream);
+        let mut greeting = String::new();
+        stream.read_line(&mut greeting).await.map_err(|e| ActionError::Mpd(e.to_string()))?;
+        if !greeting.starts_with("OK MPD ") {
+            return Err(ActionError::Mpd(format!("{} is not MPD: {:?}", address, greeting.trim_end())));
+        }
+        log::info!("MPD: connected to {} ({})", address, greeting.trim_end());
+        Ok(stream)
+    };
+    let stream = tokio::time::timeout(TIMEOUT, handshake)
+        .await
+        .map_err(|_| ActionError::Mpd(format!("{} did not answer within {:?}", address, TIMEOUT)))??;
+    Ok(Connection { server: server.clone(), stream })
+}
+
+impl Connection {
+    /// One command. The outer error is the connection failing, the inner
+    /// one MPD answering ACK.
+    async fn call(&mut self, command: &str) -> std::io::Result<Result<Vec<(String, String)>, ActionError>> {
+        self.stream.get_mut().write_all(format!("{}\n", command).as_bytes()).await?;
+        let mut pairs = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            match parse_line(line.trim_end_matches('\n')) {
+                Line::Ok => return Ok(Ok(pairs)),
+                Line::Ack(message) => return Ok(Err(ActionError::Mpd(message.to_string()))),
+                Line::Pair(key, value) => pairs.push((key.to_string(), value.to_string())),
+                Line::Other => log::debug!("MPD: unexpected line {:?}", line),
+            }
+        }
+    }
+}
+
+enum Line<'a> {
+    Ok,
+    /// The message of an ACK line, with the error code and command
+    Ack(&'a str),
+    Pair(&'a str, &'a str),
+    Other,
+}
+
+fn parse_line(line: &str) -> Line<'_> {
+    if line == "OK" {
+        return Line::Ok;
+    }
+    if let Some(ack) = line.strip_prefix("ACK ") {
+        return Line::Ack(ack);
+    }
+    match line.split_once(": ") {
+        Some((key, value)) => Line::Pair(key, value),
+        None => Line::Other,
+    }
+}
+
+/// Value of `key` in a reply
+pub(crate) fn field<'a>(reply: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    reply.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+/// The `status` reply
+pub(crate) async fn status(server: &Server) -> Result<Vec<(String, String)>, ActionError> {
+    command(server, "status").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_reply_lines() {
+        assert!(matches!(parse_line("OK"), Line::Ok));
+        assert!(matches!(
+            parse_line("ACK [52@0] {setvol} problems setting volume"),
+            Line::Ack("[52@0] {setvol} problems setting volume")
+        ));
+        assert!(matches!(parse_line("volume: 42"), Line::Pair("volume", "42")));
+        // Only the first ": " separates key and value
+        assert!(matches!(parse_line("Title: Intro: Live"), Line::Pair("Title", "Intro: Live")));
+        assert!(matches!(parse_line("binary"), Line::Other));
+        assert!(matches!(parse_line("OK MPD 0.23.5"), Line::Other));
+    }
+
+    #[test]
+    fn finds_fields() {
+        let reply = vec![
+            ("volume".to_string(), "-1".to_string()),
+            ("state".to_string(), "play".to_string()),
+            ("elapsed".to_string(), "12.345".to_string()),
+        ];
+        assert_eq!(field(&reply, "state"), Some("play"));
+        assert_eq!(field(&reply, "elapsed"), Some("12.345"));
+        assert_eq!(field(&reply, "song"), None);
+    }
+
+    /// A server that greets, then answers each command line with the next
+    /// canned reply
+    async fn canned(replies: &'static [&'static str]) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            stream.get_mut().write_all(b"OK MPD 0.23.5\n").await.unwrap();
+            for reply in replies {
+                let mut line = String::new();
+                stream.read_line(&mut line).await.unwrap();
+                stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        Server { host: "127.0.0.1".to_string(), port }
+    }
+
+    #[tokio::test]
+    async fn command_reads_pairs_and_acks() {
+        let server = canned(&["volume: 40\nstate: pause\nOK\n", "ACK [50@0] {seekcur} Not playing\n", "OK\n"]).await;
+        let status = status(&server).await.unwrap();
+        assert_eq!(field(&status, "volume"), Some("40"));
+        assert_eq!(field(&status, "state"), Some("pause"));
+        match command(&server, "seekcur +5").await {
+            Err(ActionError::Mpd(message)) => assert_eq!(message, "[50@0] {seekcur} Not playing"),
+            other => panic!("expected an ACK, got {:?}", other.map(|_| ())),
+        }
+        // An ACK leaves the connection usable
+        assert!(command(&server, "pause 1").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+/*!
+ * MPRIS player control over D-Bus, built with the `mpris` feature
+ *
+ * Talks to `org.mpris.MediaPlayer2.*` players directly so playerctl is not
+ * needed. Every call returns None when the session bus or a player is not
+ * available, and the caller falls back to playerctl.
+ */
+
+use crate::error::ActionError;
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+use zbus::Connection;
+use zbus::fdo::DBusProxy;
+use zbus::proxy::{Builder, CacheProperties};
+use zbus::zvariant::{ObjectPath, OwnedValue};
+
+const BUS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Session bus connection, opened on first use; None if there is no bus
+static SESSION: OnceCell<Option<Connection>> = OnceCell::const_new();
+
+/// A player method to call
+pub(crate) enum Call {
+    Next,
+    Previous,
+    PlayPause,
+    /// Relative seek in seconds
+    Seek(f64),
+}
+
+/// Run `call` on the pinned player, or the active one when `player` is
+/// unset. `player` is a bus name or its short form, e.g. "spotify".
+pub(crate) async fn call(player: Option<&str>, call: Call) -> Option<Result<(), ActionError>> {
+    let conn = session().await?;
+    let name = find_player(conn, player.filter(|p| !p.is_empty())).await?;
+    let proxy = player_proxy(conn, &name).await?;
+
+    log::info!("MPRIS: {} on {}", call.method(), name);
+    if crate::commands::dry_run() {
+        return Some(Ok(()));
+    }
+    let result = match call {
+        Call::Seek(seconds) => {
+            if !proxy.get_property::<bool>("CanSeek").await.unwrap_or(false) {
+                return Some(Err(ActionError::Mpris(format!("{} cannot seek", name))));
+            }
+            proxy.call_method("Seek", &((seconds * 1e6) as i64)).await
+        }
+        _ => proxy.call_method(call.method(), &()).await,
+    };
+    Some(result.map(|_| ()).map_err(|e| ActionError::Mpris(format!("{} on {}: {}", call.method(), name, e))))
+}
+
+/// Running players by short name, e.g. "spotify" or
+/// "firefox.instance_1_84"
+pub(crate) async fn players() -> Option<Vec<String>> {
+    let names = DBusProxy::new(session().await?).await.ok()?.list_names().await.ok()?;
+    Some(names.iter().filter_map(|n| n.strip_prefix(BUS_PREFIX)).map(str::to_string).collect())
+}
+
+async fn session() -> Option<&'static Connection> {
+    SESSION
+        .get_or_init(|| async {
+            Connection::session()
+                .await
+                .inspect_err(|e| log::warn!("MPRIS: no session bus, using playerctl: {}", e))
+                .ok()
+        })
+        .await
+        .as_ref()
+}
+
+/// A playback rate change
+pub(crate) enum RateChange {
+    /// Add to the current rate
+    Step(f64),
+    /// Back to normal speed
+    Reset,
+}
+
+/// Change the playback rate of the pinned or active player, clamped to its
+/// MinimumRate and MaximumRate
+pub(crate) async fn set_rate(player: Option<&str>, change: RateChange) -> Option<Result<(), ActionError>> {
+    let conn = session().await?;
+    let name = find_player(conn, player.filter(|p| !p.is_empty())).await?;
+    let proxy = player_proxy(conn, &name).await?;
+
+    let unsupported = || ActionError::Unsupported(format!("{} does not support changing the playback rate", name));
+    if !proxy.get_property::<bool>("CanControl").await.unwrap_or(false) {
+        return Some(Err(unsupported()));
+    }
+    let Ok(rate) = proxy.get_property::<f64>("Rate").await else {
+        return Some(Err(unsupported()));
+    };
+    let min = proxy.get_property::<f64>("MinimumRate").await.unwrap_or(1.0);
+    let max = proxy.get_property::<f64>("MaximumRate").await.unwrap_or(1.0);
+    if min >= max {
+        return Some(Err(unsupported()));
+    }
+    let target = match change {
+        // Rounded so repeated steps do not drift off 0.1 multiples
+        RateChange::Step(step) => ((rate + step) * 100.0).round() / 100.0,
+        RateChange::Reset => 1.0,
+    }
+    .clamp(min, max);
+
+    log::info!("MPRIS: Rate {} -> {} on {}", rate, target, name);
+    if crate::commands::dry_run() {
+        return Some(Ok(()));
+    }
+    let result = proxy.set_property("Rate", target).await;
+    Some(result.map_err(|e| ActionError::Mpris(format!("Rate on {}: {}", name, e))))
+}
+
+/// Length of the current track and the position in it, in seconds. None
+/// when the player does not report a length, as for streams.
+pub(crate) async fn progress(player: Option<&str>) -> Option<(f64, f64)> {
+    let conn = session().await?;
+    let name = find_player(conn, player.filter(|p| !p.is_empty())).await?;
+    let proxy = player_proxy(conn, &name).await?;
+    let metadata = proxy.get_property::<HashMap<String, OwnedValue>>("Metadata").await.ok()?;
+    // The spec says int64, but some players send uint64
+    let length = metadata.get("mpris:length").and_then(|length| {
+        length.downcast_ref::<i64>().ok().or_else(|| length.downcast_ref::<u64>().ok().and_then(|l| i64::try_from(l).ok()))
+    })?;
+    let position = proxy.get_property::<i64>("Position").await.ok()?;
+    (length > 0).then(|| (length as f64 / 1e6, position as f64 / 1e6))
+}
+
+/// Jump to `seconds` into the current track of the pinned or active player
+pub(crate) async fn set_position(player: Option<&str>, seconds: f64) -> Option<Result<(), ActionError>> {
+    let conn = session().await?;
+    let name = find_player(conn, player.filter(|p| !p.is_empty())).await?;
+    let proxy = player_proxy(conn, &name).await?;
+    let metadata = proxy.get_property::<HashMap<String, OwnedValue>>("Metadata").await.ok()?;
+    // SetPosition is ignored unless it names the current track
+    let track = metadata.get("mpris:trackid").and_then(|id| {
+        id.downcast_ref::<ObjectPath>()
+            .ok()
+            .or_else(|| id.downcast_ref::<String>().ok().and_then(|id| ObjectPath::try_from(id).ok()))
+    })?;
+
+    log::info!("MPRIS: SetPosition {:.1}s on {}", seconds, name);
+    if crate::commands::dry_run() {
+        return Some(Ok(()));
+    }
+    if !proxy.get_property::<bool>("CanSeek").await.unwrap_or(false) {
+        return Some(Err(ActionError::Mpris(format!("{} cannot seek", name))));
+    }
+    let result = proxy.call_method("SetPosition", &(track, (seconds * 1e6) as i64)).await;
+    Some(result.map(|_| ()).map_err(|e| ActionError::Mpris(format!("SetPosition on {}: {}", name, e))))
+}
+
+/// Playback rate of the pinned or active player
+pub(crate) async fn rate(player: Option<&str>) -> Option<f64> {
+    let conn = session().await?;
+    let name = find_player(conn, player.filter(|p| !p.is_empty())).await?;
+    player_proxy(conn, &name).await?.get_property::<f64>("Rate").await.ok()
+}
+
+impl Call {
+    fn method(&self) -> &'static str {
+        match self {
+            Call::Next => "Next",
+            Call::Previous => "Previous",
+            Call::PlayPause => "PlayPause",
+            Call::Seek(_) => "Seek",
+        }
+    }
+}
+
+/// Bus name of the player to control: the pinned one if it is running,
+/// otherwise the first playing player, otherwise the first player
+async fn find_player(conn: &Connection, pinned: Option<&str>) -> Option<String> {
+    let names = DBusProxy::new(conn).await.ok()?.list_names().await.ok()?;
+    let players: Vec<String> = names
+        .into_iter()
+        .map(|n| n.to_string())
+        .filter(|n| n.starts_with(BUS_PREFIX))
+        .collect();
+
+    if let Some(pinned) = pinned {
+        let short = format!("{}{}", BUS_PREFIX, pinned);
+        return players
+            .into_iter()
+            .find(|n| n == pinned || *n == short || n.starts_with(&format!("{}.", short)));
+    }
+
+    for name in &players {
+        if playback_status(conn, name).await.as_deref() == Some("Playing") {
+            return Some(name.clone());
+        }
+    }
+    players.into_iter().next()
+}
+
+async fn playback_status(conn: &Connection, name: &str) -> Option<String> {
+    let proxy = player_proxy(conn, name).await?;
+    proxy.get_property::<String>("PlaybackStatus").await.ok()
+}
+
+/// Proxy for a player's Player interface; properties are read fresh on
+/// every call rather than cached
+async fn player_proxy<'a>(conn: &Connection, name: &'a str) -> Option<zbus::Proxy<'a>> {
+    Builder::new(conn)
+        .destination(name)
+        .ok()?
+        .path(PLAYER_PATH)
+        .ok()?
+        .interface(PLAYER_INTERFACE)
+        .ok()?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+        .ok()
+}
@@ -0,0 +1,212 @@
+/*!
+ * OBS Studio over obs-websocket v5
+ *
+ * One connection is opened on first use and kept for later rotations; when
+ * OBS has closed it in the meantime, e.g. after a restart, the request is
+ * retried once on a fresh one. No events are subscribed to, so every frame
+ * after Identified is a reply.
+ */
+
+use crate::error::ActionError;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// How long connecting and identifying, or one request, may take
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Close code obs-websocket sends for a wrong password
+const AUTHENTICATION_FAILED: u16 = 4009;
+
+/// Lowest and highest input volume OBS accepts
+pub(crate) const MIN_VOLUME_DB: f64 = -100.0;
+pub(crate) const MAX_VOLUME_DB: f64 = 26.0;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Where the obs-websocket server is, from the dial's settings
+#[derive(Clone, PartialEq)]
+pub(crate) struct Server {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) password: String,
+}
+
+impl Server {
+    fn url(&self) -> String {
+        format!("ws://{}:{}", self.host, self.port)
+    }
+}
+
+struct Connection {
+    server: Server,
+    socket: Socket,
+    next_id: u64,
+}
+
+static CONNECTION: Mutex<Option<Connection>> = Mutex::const_new(None);
+
+/// Send one request and return its responseData (Null when there is none).
+/// Requests are serialized over the shared connection.
+pub(crate) async fn request(server: &Server, request_type: &str, data: Value) -> Result<Value, ActionError> {
+    let mut connection = CONNECTION.lock().await;
+    // A kept connection may have been closed by OBS since its last use
+    let mut retry = connection.as_ref().is_some_and(|conn| conn.server == *server);
+    loop {
+        if connection.as_ref().is_none_or(|conn| conn.server != *server) {
+            *connection = None;
+            *connection = Some(connect(server).await?);
+        }
+        let conn = connection.as_mut().expect("connected above");
+        match tokio::time::timeout(TIMEOUT, conn.call(request_type, &data)).await {
+            Ok(Ok(reply)) => return reply,
+            Ok(Err(e)) => {
+                *connection = None;
+                if !retry {
+                    return Err(ActionError::Obs(format!("{}: connection lost: {}", request_type, e)));
+                }
+                log::debug!("OBS: connection lost ({}), reconnecting", e);
+                retry = false;
+            }
+            Err(_) => {
+                *connection = None;
+                return Err(ActionError::Obs(format!("{}: no reply within {:?}", request_type, TIMEOUT)));
+            }
+        }
+    }
+}
+
+/// Connect and identify, answering the authentication challenge if OBS
+/// sets one
+async fn connect(server: &Server) -> Result<Connection, ActionError> {
+    let url = server.url();
+    let handshake = async {
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.map_err(|e| {
+            log::debug!("OBS: connecting to {}: {}", url, e);
+            ActionError::Unavailable(format!("OBS is not running at {} or its WebSocket server is off (Tools → WebSocket Server Settings)", url))
+        })?;
+        let hello = read(&mut socket).await.map_err(ActionError::Obs)?;
+        let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": 0 });
+        if let Some(auth) = hello["d"].get("authentication") {
+            let (Some(challenge), Some(salt)) = (auth["challenge"].as_str(), auth["salt"].as_str()) else {
+                return Err(ActionError::Obs("malformed authentication challenge".to_string()));
+            };
+            identify["authentication"] = authentication(&server.password, salt, challenge).into();
+        }
+        send(&mut socket, 1, identify).await.map_err(ActionError::Obs)?;
+        read(&mut socket).await.map_err(ActionError::Obs)?;
+        log::info!("OBS: connected to {}", url);
+        Ok(socket)
+    };
+    let socket = tokio::time::timeout(TIMEOUT, handshake)
+        .await
+        .map_err(|_| ActionError::Obs(format!("{} did not answer within {:?}", url, TIMEOUT)))??;
+    Ok(Connection { server: server.clone(), socket, next_id: 0 })
+}
+
+/// The Identify secret: base64(sha256(base64(sha256(password + salt)) + challenge))
+fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+impl Connection {
+    /// One request. The outer error is the connection failing, the inner
+    /// one OBS turning the request down.
+    async fn call(&mut self, request_type: &str, data: &Value) -> Result<Result<Value, ActionError>, String> {
+        self.next_id += 1;
+        let id = self.next_id.to_string();
+        let request = json!({ "requestType": request_type, "requestId": id, "requestData": data });
+        send(&mut self.socket, 6, request).await?;
+        loop {
+            let reply = read(&mut self.socket).await?;
+            if reply["op"] != 7 || reply["d"]["requestId"] != id.as_str() {
+                continue;
+            }
+            let status = &reply["d"]["requestStatus"];
+            if status["result"].as_bool() != Some(true) {
+                let comment = status["comment"].as_str().unwrap_or("no reason given");
+                return Ok(Err(ActionError::Obs(format!("{} failed ({}): {}", request_type, status["code"], comment))));
+            }
+            return Ok(Ok(reply["d"]["responseData"].clone()));
+        }
+    }
+}
+
+async fn send(socket: &mut Socket, op: u8, data: Value) -> Result<(), String> {
+    let message = json!({ "op": op, "d": data }).to_string();
+    socket.send(Message::Text(message.into())).await.map_err(|e| e.to_string())
+}
+
+/// Next JSON message, skipping pings and pongs
+async fn read(socket: &mut Socket) -> Result<Value, String> {
+    while let Some(message) = socket.next().await {
+        match message.map_err(|e| e.to_string())? {
+            Message::Text(text) => return serde_json::from_str(text.as_str()).map_err(|e| e.to_string()),
+            Message::Close(Some(frame)) if frame.code == CloseCode::from(AUTHENTICATION_FAILED) => {
+                return Err("authentication failed; check obs_password".to_string());
+            }
+            Message::Close(frame) => {
+                return Err(frame.map_or("closed".to_string(), |frame| format!("closed: {}", frame.reason)));
+            }
+            _ => {}
+        }
+    }
+    Err("closed".to_string())
+}
+
+/// Volume of an input in dB
+pub(crate) async fn input_volume(server: &Server, input: &str) -> Result<f64, ActionError> {
+    let reply = request(server, "GetInputVolume", json!({ "inputName": input })).await?;
+    reply["inputVolumeDb"]
+        .as_f64()
+        .ok_or_else(|| ActionError::Obs(format!("no volume for input {:?}", input)))
+}
+
+pub(crate) async fn set_input_volume(server: &Server, input: &str, db: f64) -> Result<(), ActionError> {
+    request(server, "SetInputVolume", json!({ "inputName": input, "inputVolumeDb": db })).await?;
+    Ok(())
+}
+
+/// The program scene and all scene names, top to bottom as in the OBS
+/// scene list
+pub(crate) async fn scenes(server: &Server) -> Result<(String, Vec<String>), ActionError> {
+    let reply = request(server, "GetSceneList", Value::Null).await?;
+    let current = reply["currentProgramSceneName"].as_str().unwrap_or_default().to_string();
+    // obs-websocket lists scenes bottom to top
+    let scenes = names(&reply["scenes"], "sceneName").into_iter().rev().collect();
+    Ok((current, scenes))
+}
+
+pub(crate) async fn set_scene(server: &Server, scene: &str) -> Result<(), ActionError> {
+    request(server, "SetCurrentProgramScene", json!({ "sceneName": scene })).await?;
+    Ok(())
+}
+
+/// Move the preview scene to program; OBS refuses outside studio mode
+pub(crate) async fn transition(server: &Server) -> Result<(), ActionError> {
+    request(server, "TriggerStudioModeTransition", Value::Null).await?;
+    Ok(())
+}
+
+/// Input names, for the inspector's picker
+pub(crate) async fn inputs(server: &Server) -> Result<Vec<String>, ActionError> {
+    let reply = request(server, "GetInputList", Value::Null).await?;
+    Ok(names(&reply["inputs"], "inputName"))
+}
+
+fn names(list: &Value, key: &str) -> Vec<String> {
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item[key].as_str().map(str::to_string))
+        .collect()
+}
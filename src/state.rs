@@ -0,0 +1,649 @@
+/*!
+ * Runtime state shared between dial events
+ *
+ * Settings travel with every OpenAction event; this only holds what the
+ * plugin has to remember between events, such as timers and counters.
+ */
+
+use crate::{ActionMode, RotateSettings};
+use crate::commands::on_path;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::task::JoinHandle;
+
+/// Runtime state of a single dial, keyed by action instance id
+#[derive(Default)]
+struct DialState {
+    last_tick: Option<Instant>,
+    last_release: Option<Instant>,
+    /// The current press was dropped as bounce, so its release is dropped too
+    press_suppressed: bool,
+    /// Timer that fires the long press action while the dial is held
+    long_press: Option<JoinHandle<()>>,
+    /// The long press action ran for the current press
+    long_press_fired: bool,
+    held: bool,
+    /// The dial was turned during the current press, or the press was used
+    /// up otherwise
+    rotated_while_held: bool,
+    /// Single press action waiting out the double press window
+    pending_press: Option<JoinHandle<()>>,
+    /// Ticks counted towards the next action; the sign is the direction
+    tick_accumulator: i64,
+    /// Signed steps collected during the current coalescing window
+    pending_steps: i64,
+    /// Timer that runs the collected steps when the window closes
+    flush: Option<JoinHandle<()>>,
+    /// Timer that clears the level overlay
+    overlay_clear: Option<JoinHandle<()>>,
+    /// Mode whose mute state the dial shows as its state, when one of its
+    /// press actions toggles mute
+    mute_mode: Option<ActionMode>,
+    /// Device the action sits on, for the status socket
+    device_id: String,
+    /// Rotate mode, unset for press actions
+    mode: Option<ActionMode>,
+    last_input: Option<SystemTime>,
+}
+
+/// Per-rotation log lines of one kind and mode in the current burst window
+#[derive(Default)]
+struct LogBurst {
+    started: Option<Instant>,
+    /// Lines held back since `started`
+    held_back: u32,
+}
+
+/// At most one per-rotation log line of each kind and mode goes out at info
+/// per window; the rest are summed up when it closes
+const LOG_BURST_WINDOW: Duration = Duration::from_secs(1);
+
+/// External tools the modes can use
+const TOOLS: &[&str] = &["amixer", "wpctl", "pactl", "osascript", "playerctl", "xdotool", "ydotool", "brightnessctl", "brightness", "ddcutil", "gammastep", "redshift", "wlsunset", "swaymsg", "i3-msg", "hyprctl"];
+
+/// What the desktop session offers, probed once when the plugin starts
+#[derive(Debug, Serialize)]
+pub(crate) struct BackendCapabilities {
+    /// XDG_SESSION_TYPE, e.g. "x11" or "wayland"
+    pub(crate) session_type: String,
+    /// A Wayland compositor is reachable (WAYLAND_DISPLAY is set)
+    pub(crate) wayland: bool,
+    /// An X server is reachable (DISPLAY is set), natively or through XWayland
+    pub(crate) x11: bool,
+    /// Tools from `TOOLS` found on PATH
+    pub(crate) tools: Vec<&'static str>,
+}
+
+impl BackendCapabilities {
+    fn probe() -> Self {
+        let set = |var| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+        let capabilities = Self {
+            session_type: std::env::var("XDG_SESSION_TYPE").unwrap_or_default(),
+            wayland: set("WAYLAND_DISPLAY"),
+            x11: set("DISPLAY"),
+            tools: TOOLS.iter().copied().filter(|tool| on_path(tool)).collect(),
+        };
+        log::info!("Backend capabilities: {:?}", capabilities);
+        capabilities
+    }
+
+    pub(crate) fn has(&self, tool: &str) -> bool {
+        self.tools.contains(&tool)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct PluginState {
+    dials: Mutex<HashMap<String, DialState>>,
+    capabilities: OnceLock<BackendCapabilities>,
+    /// Last known mute state of the Volume and MicVolume targets
+    muted: Mutex<HashMap<ActionMode, bool>>,
+    /// Per-rotation log throttle, by line kind and mode
+    log_bursts: Mutex<HashMap<(&'static str, ActionMode), LogBurst>>,
+    /// Player picked in PlayerSelect mode, by name without instance suffix
+    selected_player: Mutex<Option<String>>,
+    /// Color temperature ColorTemp mode last applied, in kelvin
+    color_temp: Mutex<Option<u32>>,
+    /// Connected devices by id, with their names
+    devices: Mutex<HashMap<String, String>>,
+    /// Mode given to rotate actions that appear without one, by encoder index
+    default_modes: OnceLock<HashMap<u8, ActionMode>>,
+    /// ALSA mixer for the alsa volume backend, opened on first use
+    #[cfg(feature = "alsa")]
+    mixer: Mutex<Option<alsa::Mixer>>,
+}
+
+/// Snapshot written to status socket clients
+#[cfg(unix)]
+#[derive(Serialize)]
+pub(crate) struct Status {
+    devices: Vec<DeviceStatus>,
+    dials: Vec<DialStatus>,
+    /// Most recent rotation or press on any dial, in Unix milliseconds
+    last_input_ms: Option<u64>,
+}
+
+#[cfg(unix)]
+#[derive(Serialize)]
+struct DeviceStatus {
+    id: String,
+    name: String,
+}
+
+#[cfg(unix)]
+#[derive(Serialize)]
+struct DialStatus {
+    instance_id: String,
+    device_id: String,
+    mode: Option<ActionMode>,
+    held: bool,
+    last_input_ms: Option<u64>,
+}
+
+pub(crate) static STATE: LazyLock<PluginState> = LazyLock::new(PluginState::default);
+
+impl PluginState {
+    /// The probed session and tools; probes on first call
+    pub(crate) fn capabilities(&self) -> &BackendCapabilities {
+        self.capabilities.get_or_init(BackendCapabilities::probe)
+    }
+
+    /// Mode for a rotate action on `encoder` that has none set yet. Unknown
+    /// encoders get Volume.
+    pub(crate) fn default_mode(&self, encoder: Option<u8>) -> ActionMode {
+        let modes = self.default_modes.get_or_init(default_modes);
+        encoder.and_then(|encoder| modes.get(&encoder)).cloned().unwrap_or_default()
+    }
+
+    /// Whether a per-rotation log line of `kind` for `mode` should go out at
+    /// info. The first line of a burst does; the others within
+    /// `LOG_BURST_WINDOW` are counted and reported as one "Volume x5" line
+    /// when the window closes.
+    pub(crate) fn log_allowed(&self, kind: &'static str, mode: &ActionMode) -> bool {
+        let mut bursts = self.log_bursts.lock().unwrap();
+        let burst = bursts.entry((kind, mode.clone())).or_default();
+        let now = Instant::now();
+        match burst.started {
+            Some(started) if now.duration_since(started) < LOG_BURST_WINDOW => {
+                burst.held_back += 1;
+                if burst.held_back == 1 {
+                    let mode = mode.clone();
+                    let remaining = LOG_BURST_WINDOW - now.duration_since(started);
+                    tokio::spawn(async move {
+                        tokio::time::sleep(remaining).await;
+                        STATE.flush_log_burst(kind, &mode);
+                    });
+                }
+                false
+            }
+            _ => {
+                // The summary timer may not have run yet
+                log_burst_summary(kind, mode, std::mem::take(&mut burst.held_back));
+                burst.started = Some(now);
+                true
+            }
+        }
+    }
+
+    fn flush_log_burst(&self, kind: &'static str, mode: &ActionMode) {
+        let mut bursts = self.log_bursts.lock().unwrap();
+        if let Some(burst) = bursts.get_mut(&(kind, mode.clone())) {
+            log_burst_summary(kind, mode, std::mem::take(&mut burst.held_back));
+        }
+    }
+
+    /// Count `ticks` in `direction` towards `ticks_per_action` and return how
+    /// many actions they complete. The remainder carries over to the next
+    /// tick in the same direction; reversing starts the count afresh.
+    pub(crate) fn divide_ticks(&self, instance_id: &str, direction: i8, ticks: u32, settings: &RotateSettings) -> u32 {
+        let per_action = i64::from(settings.ticks_per_action.max(1));
+        if per_action == 1 {
+            return ticks;
+        }
+
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        if dial.tick_accumulator.signum() != i64::from(direction) {
+            dial.tick_accumulator = 0;
+        }
+        dial.tick_accumulator += i64::from(direction) * i64::from(ticks);
+        let actions = dial.tick_accumulator / per_action;
+        dial.tick_accumulator %= per_action;
+        actions.unsigned_abs() as u32
+    }
+
+    /// Scale `steps` by the acceleration multiplier for fast spins
+    pub(crate) fn rotation_magnitude(&self, instance_id: &str, steps: u32, settings: &RotateSettings) -> u32 {
+        let now = Instant::now();
+
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        match dial.last_tick.replace(now) {
+            Some(last) => steps.saturating_mul(settings.acceleration_multiplier(now.duration_since(last))),
+            None => steps,
+        }
+    }
+
+    /// Record a dial press. Returns false if it is bounce from the previous release.
+    pub(crate) fn press_down(&self, instance_id: &str, settings: &RotateSettings) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        let debounce = Duration::from_millis(settings.press_debounce_ms);
+        dial.press_suppressed = dial
+            .last_release
+            .is_some_and(|last| last.elapsed() < debounce);
+        if dial.press_suppressed {
+            return false;
+        }
+        dial.held = true;
+        dial.rotated_while_held = false;
+        true
+    }
+
+    /// Record a rotation. Returns true if the dial is held, in which case the
+    /// pending press and long press actions for this press are cancelled.
+    pub(crate) fn rotate_while_held(&self, instance_id: &str, pressed: bool) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        if !(pressed || dial.held) {
+            return false;
+        }
+        dial.rotated_while_held = true;
+        if let Some(timer) = dial.long_press.take() {
+            timer.abort();
+        }
+        true
+    }
+
+    /// Mark the current press as used up, e.g. to end a window switch, so
+    /// its release runs no press action
+    pub(crate) fn consume_press(&self, instance_id: &str) {
+        let mut dials = self.dials.lock().unwrap();
+        dials.entry(instance_id.to_string()).or_default().rotated_while_held = true;
+    }
+
+    /// Record a dial release, cancelling any pending long press timer.
+    /// Returns false if the press was suppressed, already handled as a long
+    /// press, or used to modify a rotation.
+    pub(crate) fn press_up(&self, instance_id: &str) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        if let Some(timer) = dial.long_press.take() {
+            timer.abort();
+        }
+        if std::mem::take(&mut dial.press_suppressed) {
+            return false;
+        }
+        dial.held = false;
+        dial.last_release = Some(Instant::now());
+        let long_press_fired = std::mem::take(&mut dial.long_press_fired);
+        let rotated = std::mem::take(&mut dial.rotated_while_held);
+        !(long_press_fired || rotated)
+    }
+
+    pub(crate) fn start_long_press(&self, instance_id: &str, timer: JoinHandle<()>) {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        if let Some(previous) = dial.long_press.replace(timer) {
+            previous.abort();
+        }
+    }
+
+    /// Claim the pending long press from its timer. Returns false if the
+    /// dial was released in the meantime.
+    pub(crate) fn fire_long_press(&self, instance_id: &str) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        let Some(dial) = dials.get_mut(instance_id) else {
+            return false;
+        };
+        if dial.long_press.take().is_none() {
+            return false;
+        }
+        dial.long_press_fired = true;
+        true
+    }
+
+    /// Hold a single press back for `window` in case a second one follows.
+    /// Returns true if a press was already waiting, meaning this release
+    /// completes a double press and the waiting one is dropped. Otherwise
+    /// `single` runs once the window passes without another release. As in
+    /// `coalesce`, the timer is spawned and stored under the same lock.
+    pub(crate) fn defer_press(
+        &'static self,
+        instance_id: &str,
+        window: Duration,
+        single: impl Future<Output = ()> + Send + 'static,
+    ) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        if let Some(timer) = dial.pending_press.take() {
+            timer.abort();
+            return true;
+        }
+        let instance_id = instance_id.to_string();
+        dial.pending_press = Some(tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            if self.fire_pending_press(&instance_id) {
+                single.await;
+            }
+        }));
+        false
+    }
+
+    /// Claim the pending single press from its timer. Returns false if a
+    /// second press cancelled it in the meantime.
+    pub(crate) fn fire_pending_press(&self, instance_id: &str) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        dials
+            .get_mut(instance_id)
+            .is_some_and(|dial| dial.pending_press.take().is_some())
+    }
+
+    /// Add steps to the coalescing window. The first steps of a window
+    /// start its timer, which after `window` passes the net steps to
+    /// `flush` unless they cancelled out. The timer is spawned and stored
+    /// under the same lock, so it cannot close the window before its
+    /// handle is recorded.
+    pub(crate) fn coalesce(&'static self, instance_id: &str, steps: i64, window: Duration, flush: impl FnOnce(i64) + Send + 'static) {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        dial.pending_steps += steps;
+        if dial.flush.is_some() {
+            return;
+        }
+        let instance_id = instance_id.to_string();
+        dial.flush = Some(tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            let steps = self.take_steps(&instance_id);
+            if steps != 0 {
+                flush(steps);
+            }
+        }));
+    }
+
+    /// Close the coalescing window and take the net steps collected in it
+    pub(crate) fn take_steps(&self, instance_id: &str) -> i64 {
+        let mut dials = self.dials.lock().unwrap();
+        let Some(dial) = dials.get_mut(instance_id) else {
+            return 0;
+        };
+        dial.flush = None;
+        std::mem::take(&mut dial.pending_steps)
+    }
+
+    /// Schedule clearing the level overlay, replacing any earlier timer so
+    /// rapid rotations keep the overlay up instead of flashing it
+    pub(crate) fn start_overlay_clear(&self, instance_id: &str, timer: JoinHandle<()>) {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        if let Some(previous) = dial.overlay_clear.replace(timer) {
+            previous.abort();
+        }
+    }
+
+    /// Claim the overlay clear from its timer. Returns false if a newer
+    /// rotation rescheduled it in the meantime.
+    pub(crate) fn fire_overlay_clear(&self, instance_id: &str) -> bool {
+        let mut dials = self.dials.lock().unwrap();
+        dials
+            .get_mut(instance_id)
+            .is_some_and(|dial| dial.overlay_clear.take().is_some())
+    }
+
+    /// Run `f` on the default ALSA mixer, opening it if needed. On error the
+    /// mixer is reopened and `f` retried once, so a restarted sound server or
+    /// replugged card does not leave a dead handle behind.
+    #[cfg(feature = "alsa")]
+    pub(crate) fn with_mixer<T>(&self, f: impl Fn(&alsa::Mixer) -> alsa::Result<T>) -> alsa::Result<T> {
+        let mut mixer = self.mixer.lock().unwrap();
+        if let Some(open) = mixer.as_ref()
+            && let Ok(result) = f(open)
+        {
+            return Ok(result);
+        }
+        *mixer = None;
+        let reopened = alsa::Mixer::new("default", false)?;
+        let result = f(&reopened);
+        *mixer = Some(reopened);
+        result
+    }
+
+    /// Show the mute state of `mode` on the dial, or stop with None
+    pub(crate) fn watch_mute(&self, instance_id: &str, mode: Option<ActionMode>) {
+        self.dials.lock().unwrap().entry(instance_id.to_string()).or_default().mute_mode = mode;
+    }
+
+    /// Dials showing the mute state of `mode`
+    pub(crate) fn mute_watchers(&self, mode: &ActionMode) -> Vec<String> {
+        let dials = self.dials.lock().unwrap();
+        dials
+            .iter()
+            .filter(|(_, dial)| dial.mute_mode.as_ref() == Some(mode))
+            .map(|(instance_id, _)| instance_id.clone())
+            .collect()
+    }
+
+    /// Record the mute state of `mode`. Returns true if it changed or was
+    /// not known yet.
+    pub(crate) fn set_muted(&self, mode: &ActionMode, muted: bool) -> bool {
+        self.muted.lock().unwrap().insert(mode.clone(), muted) != Some(muted)
+    }
+
+    pub(crate) fn selected_player(&self) -> Option<String> {
+        self.selected_player.lock().unwrap().clone()
+    }
+
+    pub(crate) fn select_player(&self, player: Option<String>) {
+        *self.selected_player.lock().unwrap() = player;
+    }
+
+    pub(crate) fn color_temp(&self) -> Option<u32> {
+        *self.color_temp.lock().unwrap()
+    }
+
+    pub(crate) fn set_color_temp(&self, temp: u32) {
+        *self.color_temp.lock().unwrap() = Some(temp);
+    }
+
+    pub(crate) fn device_connected(&self, device_id: &str, name: &str) {
+        self.devices.lock().unwrap().insert(device_id.to_string(), name.to_string());
+    }
+
+    pub(crate) fn device_disconnected(&self, device_id: &str) {
+        self.devices.lock().unwrap().remove(device_id);
+    }
+
+    /// Remember where an action sits and, for rotate actions, its mode
+    pub(crate) fn track(&self, instance_id: &str, device_id: &str, mode: Option<ActionMode>) {
+        let mut dials = self.dials.lock().unwrap();
+        let dial = dials.entry(instance_id.to_string()).or_default();
+        dial.device_id = device_id.to_string();
+        dial.mode = mode;
+    }
+
+    /// Stamp a rotation or press, bounce included, so the status socket
+    /// shows the dial is being seen
+    pub(crate) fn record_input(&self, instance_id: &str) {
+        self.dials.lock().unwrap().entry(instance_id.to_string()).or_default().last_input = Some(SystemTime::now());
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn status(&self) -> Status {
+        let mut devices: Vec<DeviceStatus> = self
+            .devices
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, name)| DeviceStatus { id: id.clone(), name: name.clone() })
+            .collect();
+        devices.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut dials: Vec<DialStatus> = self
+            .dials
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(instance_id, dial)| DialStatus {
+                instance_id: instance_id.clone(),
+                device_id: dial.device_id.clone(),
+                mode: dial.mode.clone(),
+                held: dial.held,
+                last_input_ms: dial.last_input.map(unix_ms),
+            })
+            .collect();
+        dials.sort_by(|a, b| (&a.device_id, &a.instance_id).cmp(&(&b.device_id, &b.instance_id)));
+        let last_input_ms = dials.iter().filter_map(|dial| dial.last_input_ms).max();
+        Status { devices, dials, last_input_ms }
+    }
+
+    /// Cancel every dial's timers and forget all dials, on plugin shutdown
+    pub(crate) fn clear(&self) {
+        let instance_ids: Vec<String> = self.dials.lock().unwrap().keys().cloned().collect();
+        for instance_id in instance_ids {
+            self.remove(&instance_id);
+        }
+    }
+
+    pub(crate) fn remove(&self, instance_id: &str) {
+        if let Some(dial) = self.dials.lock().unwrap().remove(instance_id) {
+            for timer in [dial.long_press, dial.pending_press, dial.flush, dial.overlay_clear].into_iter().flatten() {
+                timer.abort();
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |since| since.as_millis() as u64)
+}
+
+fn log_burst_summary(kind: &str, mode: &ActionMode, held_back: u32) {
+    if held_back > 0 {
+        log::info!("{}: {:?} x{} more within {:?}", kind, mode, held_back, LOG_BURST_WINDOW);
+    }
+}
+
+/// Encoder to mode table: the dial (encoder 2) turns the volume and the face
+/// encoders scroll and skip tracks. N1_DEFAULT_MODES overrides single
+/// entries, e.g. "0=zoom,1=brightness".
+fn default_modes() -> HashMap<u8, ActionMode> {
+    let mut modes = HashMap::from([(0, ActionMode::Scroll), (1, ActionMode::MediaTrack), (2, ActionMode::Volume)]);
+    let table = std::env::var("N1_DEFAULT_MODES").unwrap_or_default();
+    for entry in table.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let parsed = entry.split_once('=').and_then(|(encoder, mode)| {
+            let mode = serde_json::from_value(serde_json::Value::String(mode.trim().to_string())).ok()?;
+            Some((encoder.trim().parse().ok()?, mode))
+        });
+        match parsed {
+            Some((encoder, mode)) => {
+                modes.insert(encoder, mode);
+            }
+            None => log::warn!("Ignoring invalid N1_DEFAULT_MODES entry {:?}", entry),
+        }
+    }
+    log::info!("Default modes by encoder: {:?}", modes);
+    modes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn state() -> &'static PluginState {
+        Box::leak(Box::default())
+    }
+
+    /// Feed `ticks` into one window and return the flushed batches
+    async fn batches(state: &'static PluginState, ticks: &[i64]) -> Vec<i64> {
+        let flushed = Arc::new(Mutex::new(Vec::new()));
+        for &steps in ticks {
+            let flushed = flushed.clone();
+            state.coalesce("dial", steps, Duration::from_millis(30), move |steps| flushed.lock().unwrap().push(steps));
+        }
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        flushed.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn rapid_ticks_run_as_one_batch() {
+        assert_eq!(batches(state(), &[1; 10]).await, vec![10]);
+    }
+
+    #[tokio::test]
+    async fn opposite_ticks_cancel() {
+        let state = state();
+        assert_eq!(batches(state, &[1, 1, -1]).await, vec![1]);
+        assert_eq!(batches(state, &[1, -1]).await, Vec::<i64>::new());
+        // The window reopens after a flush
+        assert_eq!(batches(state, &[-2]).await, vec![-2]);
+    }
+
+    /// Release the dial and count the single presses that fire
+    fn release(state: &'static PluginState, singles: &Arc<Mutex<u32>>) -> bool {
+        let singles = singles.clone();
+        state.defer_press("dial", Duration::from_millis(300), async move { *singles.lock().unwrap() += 1 })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_press_fires_after_the_window() {
+        let (state, singles) = (state(), Arc::default());
+        assert!(!release(state, &singles));
+        tokio::time::sleep(Duration::from_millis(299)).await;
+        assert_eq!(*singles.lock().unwrap(), 0);
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        assert_eq!(*singles.lock().unwrap(), 1);
+        assert!(!state.fire_pending_press("dial"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn second_press_in_the_window_is_a_double_press() {
+        let (state, singles) = (state(), Arc::default());
+        assert!(!release(state, &singles));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(release(state, &singles));
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(*singles.lock().unwrap(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn second_press_after_the_window_is_another_single() {
+        let (state, singles) = (state(), Arc::default());
+        assert!(!release(state, &singles));
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(!release(state, &singles));
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(*singles.lock().unwrap(), 2);
+    }
+
+    fn per_action(ticks: u32) -> RotateSettings {
+        RotateSettings { ticks_per_action: ticks, ..RotateSettings::default() }
+    }
+
+    fn accumulator(state: &PluginState) -> i64 {
+        state.dials.lock().unwrap()["dial"].tick_accumulator
+    }
+
+    #[test]
+    fn wiggling_never_reaches_the_threshold() {
+        let state = state();
+        let settings = per_action(3);
+        let actions: Vec<u32> = [1, 1, -1, 1, 1].into_iter().map(|direction| state.divide_ticks("dial", direction, 1, &settings)).collect();
+        assert_eq!(actions, vec![0; 5]);
+        // Each reversal started the count afresh, so only the last two ticks count
+        assert_eq!(accumulator(state), 2);
+        assert_eq!(state.divide_ticks("dial", 1, 1, &settings), 1);
+        assert_eq!(accumulator(state), 0);
+    }
+
+    #[test]
+    fn threshold_fires_once_across_events() {
+        let state = state();
+        let settings = per_action(3);
+        assert_eq!(state.divide_ticks("dial", 1, 2, &settings), 0);
+        assert_eq!(state.divide_ticks("dial", 1, 1, &settings), 1);
+        assert_eq!(accumulator(state), 0);
+    }
+}
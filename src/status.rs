@@ -0,0 +1,81 @@
+/*!
+ * Opt-in status socket
+ *
+ * With N1_STATUS_SOCKET set to a path, the plugin listens on a Unix socket
+ * there and writes one JSON snapshot of the devices and dials it knows about
+ * to each client before closing the connection:
+ *
+ *     socat - UNIX-CONNECT:/tmp/n1.sock
+ */
+
+use crate::state::STATE;
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+
+/// How long a client gets to take the snapshot before it is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("N1_STATUS_SOCKET").filter(|path| !path.is_empty()).map(PathBuf::from)
+}
+
+/// Remove the socket at `path` if one is there, e.g. left by a crashed run.
+/// Anything else at the path is left alone so binding fails loudly.
+fn remove_stale(path: &PathBuf) {
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Start listening when N1_STATUS_SOCKET is set. Clients are served one
+/// after another, so at most one reader is connected at a time.
+pub(crate) fn spawn() {
+    let Some(path) = socket_path() else {
+        return;
+    };
+    remove_stale(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to listen on status socket {}: {}", path.display(), e);
+            return;
+        }
+    };
+    log::info!("Status socket listening on {}", path.display());
+
+    tokio::spawn(async move {
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!("Status socket accept failed: {}", e);
+                    tokio::time::sleep(WRITE_TIMEOUT).await;
+                    continue;
+                }
+            };
+            let mut snapshot = match serde_json::to_vec_pretty(&STATE.status()) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::warn!("Failed to serialize status: {}", e);
+                    continue;
+                }
+            };
+            snapshot.push(b'\n');
+            match tokio::time::timeout(WRITE_TIMEOUT, stream.write_all(&snapshot)).await {
+                Ok(Ok(())) => log::debug!("Status snapshot sent"),
+                Ok(Err(e)) => log::debug!("Status client went away: {}", e),
+                Err(_) => log::debug!("Status client too slow, dropped"),
+            }
+        }
+    });
+}
+
+/// Remove the socket on shutdown
+pub(crate) fn shutdown() {
+    if let Some(path) = socket_path() {
+        remove_stale(&path);
+    }
+}
@@ -0,0 +1,208 @@
+/*!
+ * Virtual input device for Scroll, Zoom, Mouse Move and the keyboard modes, built with
+ * the `uinput` feature
+ *
+ * Creates a uinput mouse/keyboard on first use and writes wheel and key
+ * events to it directly, so neither xdotool nor ydotool is needed. If
+ * /dev/uinput cannot be opened the callers fall back to the external tool.
+ */
+
+use crate::error::ActionError;
+use crate::keyspec;
+use crate::modes::{Axis, SwitchKey, ZoomKey};
+use evdev::uinput::VirtualDevice;
+use evdev::{AttributeSet, EventType, InputEvent, KeyCode, RelativeAxisCode};
+use std::io;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// The virtual device, created on first use
+static DEVICE: Mutex<Option<VirtualDevice>> = Mutex::new(None);
+/// Creating the device failed; stop trying and use the external tools
+static UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Give the compositor time to pick up a freshly created device, otherwise
+/// the first events are lost
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// Scroll the wheel on `axis` by `clicks`; positive scrolls up or right.
+/// None when uinput is unavailable.
+pub(crate) async fn scroll(axis: Axis, clicks: i32) -> Option<Result<(), ActionError>> {
+    match axis {
+        Axis::Vertical => emit(&[wheel(clicks)]).await,
+        Axis::Horizontal => emit(&[InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_HWHEEL.0, clicks)]).await,
+    }
+}
+
+/// Move the pointer by `dx`, `dy` pixels (before pointer acceleration).
+/// None when uinput is unavailable.
+pub(crate) async fn mouse_move(dx: i32, dy: i32) -> Option<Result<(), ActionError>> {
+    emit(&[
+        InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_X.0, dx),
+        InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_Y.0, dy),
+    ])
+    .await
+}
+
+/// Ctrl + wheel by `clicks`; positive zooms in. None when uinput is
+/// unavailable.
+pub(crate) async fn zoom(clicks: i32) -> Option<Result<(), ActionError>> {
+    let ctrl = KeyCode::KEY_LEFTCTRL.code();
+    emit(&[
+        InputEvent::new(EventType::KEY.0, ctrl, 1),
+        wheel(clicks),
+        InputEvent::new(EventType::KEY.0, ctrl, 0),
+    ])
+    .await
+}
+
+/// Press Ctrl+`key` `repeat` times. None when uinput is unavailable.
+pub(crate) async fn ctrl_key(key: ZoomKey, repeat: u32) -> Option<Result<(), ActionError>> {
+    let ctrl = KeyCode::KEY_LEFTCTRL.code();
+    for _ in 0..repeat {
+        // Separate reports for down and up, or the press can be lost
+        for state in [1, 0] {
+            let result = emit(&[
+                InputEvent::new(EventType::KEY.0, ctrl, state),
+                InputEvent::new(EventType::KEY.0, key.code(), state),
+            ])
+            .await?;
+            if result.is_err() {
+                return Some(result);
+            }
+        }
+    }
+    Some(Ok(()))
+}
+
+/// Press `chords`, given as input codes, in order `repeat` times. None when
+/// uinput is unavailable.
+pub(crate) async fn chords(chords: &[&[u16]], repeat: u32) -> Option<Result<(), ActionError>> {
+    for _ in 0..repeat {
+        for codes in chords {
+            let down: Vec<InputEvent> = codes.iter().map(|code| InputEvent::new(EventType::KEY.0, *code, 1)).collect();
+            let up: Vec<InputEvent> = codes.iter().rev().map(|code| InputEvent::new(EventType::KEY.0, *code, 0)).collect();
+            // Separate reports for down and up, as in ctrl_key
+            for events in [&down, &up] {
+                let result = emit(events).await?;
+                if result.is_err() {
+                    return Some(result);
+                }
+            }
+        }
+    }
+    Some(Ok(()))
+}
+
+/// Press or release Alt, or tap Tab `repeat` times, for Window Switch.
+/// None when uinput is unavailable.
+pub(crate) async fn switch_key(key: SwitchKey, repeat: u32) -> Option<Result<(), ActionError>> {
+    let states: &[i32] = match key {
+        SwitchKey::AltDown => &[1],
+        SwitchKey::AltUp => &[0],
+        SwitchKey::Tab | SwitchKey::ShiftTab => &[1, 0],
+    };
+    let repeat = if states.len() == 1 { 1 } else { repeat };
+    for _ in 0..repeat {
+        // Separate reports for down and up, as in ctrl_key
+        for state in states {
+            let events: Vec<InputEvent> = key.codes().iter().map(|code| InputEvent::new(EventType::KEY.0, *code, *state)).collect();
+            let result = emit(&events).await?;
+            if result.is_err() {
+                return Some(result);
+            }
+        }
+    }
+    Some(Ok(()))
+}
+
+/// Destroy the virtual device, on plugin shutdown
+pub(crate) fn shutdown() {
+    if DEVICE.lock().unwrap().take().is_some() {
+        log::info!("uinput: virtual device removed");
+    }
+}
+
+fn wheel(clicks: i32) -> InputEvent {
+    InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_WHEEL.0, clicks)
+}
+
+async fn emit(events: &[InputEvent]) -> Option<Result<(), ActionError>> {
+    if UNAVAILABLE.load(Ordering::Relaxed) {
+        return None;
+    }
+    let created = {
+        let mut device = DEVICE.lock().unwrap();
+        if device.is_some() {
+            false
+        } else {
+            match create() {
+                Ok(created) => {
+                    *device = Some(created);
+                    true
+                }
+                Err(e) => {
+                    UNAVAILABLE.store(true, Ordering::Relaxed);
+                    if e.kind() == io::ErrorKind::PermissionDenied {
+                        log::warn!(
+                            "uinput: no access to /dev/uinput, falling back to xdotool/ydotool. Add a udev rule such as \
+                             KERNEL==\"uinput\", GROUP=\"input\", MODE=\"0660\" and add yourself to the input group"
+                        );
+                    } else {
+                        log::warn!("uinput: cannot create virtual device, falling back to xdotool/ydotool: {}", e);
+                    }
+                    return None;
+                }
+            }
+        }
+    };
+    if created {
+        tokio::time::sleep(SETTLE).await;
+    }
+
+    let mut device = DEVICE.lock().unwrap();
+    let device = device.as_mut()?;
+    Some(device.emit(events).map_err(ActionError::Uinput))
+}
+
+fn create() -> io::Result<VirtualDevice> {
+    let mut axes = AttributeSet::<RelativeAxisCode>::new();
+    for axis in [RelativeAxisCode::REL_X, RelativeAxisCode::REL_Y, RelativeAxisCode::REL_WHEEL, RelativeAxisCode::REL_HWHEEL] {
+        axes.insert(axis);
+    }
+    // A button makes libinput treat the device as a mouse
+    let mut keys = AttributeSet::<KeyCode>::new();
+    for key in [
+        KeyCode::BTN_LEFT,
+        KeyCode::KEY_LEFTCTRL,
+        KeyCode::KEY_EQUAL,
+        KeyCode::KEY_MINUS,
+        KeyCode::KEY_0,
+        KeyCode::KEY_LEFTALT,
+        KeyCode::KEY_LEFTSHIFT,
+        KeyCode::KEY_TAB,
+        KeyCode::KEY_PAGEUP,
+        KeyCode::KEY_PAGEDOWN,
+        KeyCode::KEY_LEFT,
+        KeyCode::KEY_RIGHT,
+        KeyCode::KEY_W,
+        KeyCode::KEY_Z,
+        KeyCode::KEY_Y,
+        KeyCode::KEY_S,
+        KeyCode::KEY_LEFTMETA,
+    ] {
+        keys.insert(key);
+    }
+    // Everything a Keys mode spec can name
+    for (_, code) in keyspec::KEYS {
+        keys.insert(KeyCode::new(*code));
+    }
+    let device = VirtualDevice::builder()?
+        .name("N1 Encoder Actions")
+        .with_relative_axes(&axes)?
+        .with_keys(&keys)?
+        .build()?;
+    log::info!("uinput: virtual device created");
+    Ok(device)
+}
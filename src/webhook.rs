@@ -0,0 +1,77 @@
+/*!
+ * HTTP requests for Webhook mode
+ *
+ * One request per coalesced rotation or press. The timeout is the dial's
+ * command timeout, so a slow endpoint holds up the dial no longer than a
+ * slow command would.
+ */
+
+use crate::error::ActionError;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// How much of an error response is kept for the log and the alert
+const SNIPPET_LEN: usize = 200;
+
+/// Reused for its connection pool
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// One request, from the dial's settings
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) url: String,
+    /// "Name: value" lines; blank lines are skipped
+    pub(crate) headers: String,
+    pub(crate) body: String,
+    pub(crate) timeout: Duration,
+}
+
+/// Send `request`, failing on anything but a 2xx reply
+pub(crate) async fn send(request: &Request) -> Result<(), ActionError> {
+    let url = request.url.trim();
+    if url.is_empty() {
+        return Err(ActionError::Webhook("no URL set".to_string()));
+    }
+    let method = request.method.trim().to_ascii_uppercase();
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|_| ActionError::Webhook(format!("{:?} is not an HTTP method", request.method)))?;
+    let what = format!("{} {}", method, redacted(url));
+    let mut builder = CLIENT.request(method, url).timeout(request.timeout);
+    for line in request.headers.lines().filter(|line| !line.trim().is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(ActionError::Webhook(format!("header {:?} is not \"Name: value\"", line.trim())));
+        };
+        builder = builder.header(name.trim(), value.trim());
+    }
+    if !request.body.is_empty() {
+        builder = builder.body(request.body.clone());
+    }
+    let response = builder.send().await.map_err(|e| {
+        if e.is_timeout() {
+            ActionError::Webhook(format!("{}: no reply within {:?}", what, request.timeout))
+        } else {
+            ActionError::Webhook(format!("{}: {}", what, e.without_url()))
+        }
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ActionError::Webhook(format!("{}: HTTP {} {}", what, status, snippet(&body))));
+    }
+    log::debug!("Webhook: {}: HTTP {}", what, status);
+    Ok(())
+}
+
+/// The URL without its query, which often carries a token
+pub(crate) fn redacted(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// The start of a response body on one line
+fn snippet(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match body.char_indices().nth(SNIPPET_LEN) {
+        Some((end, _)) => format!("{}…", &body[..end]),
+        None => body,
+    }
+}
@@ -0,0 +1,240 @@
+/*!
+ * Windows backends for Volume, Media Track, Scroll/Zoom and Tab Switch
+ *
+ * Volume goes through the IAudioEndpointVolume of the default render
+ * endpoint, or the capture endpoint for the microphone. Media keys, wheel
+ * and Ctrl+wheel go through SendInput; the media keys reach whichever app
+ * owns the system media session. All run in-process, so no external tools
+ * are needed on Windows.
+ */
+
+use crate::error::ActionError;
+use crate::keyspec;
+use crate::modes::{Axis, ZoomKey};
+use windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume;
+use windows::Win32::Media::Audio::{IMMDeviceEnumerator, MMDeviceEnumerator, eCapture, eConsole, eRender};
+use windows::Win32::System::Com::{CLSCTX_ALL, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT, KEYEVENTF_KEYUP, MOUSEEVENTF_HWHEEL, MOUSEEVENTF_MOVE, MOUSEEVENTF_WHEEL, MOUSEINPUT,
+    SendInput,
+    VIRTUAL_KEY, VK_0, VK_APPS, VK_BACK, VK_CAPITAL, VK_CONTROL, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_F11, VK_F12, VK_F13,
+    VK_HOME, VK_INSERT, VK_LEFT, VK_LWIN, VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_MEDIA_STOP, VK_MENU,
+    VK_NEXT, VK_OEM_1, VK_OEM_2, VK_OEM_3, VK_OEM_4, VK_OEM_5, VK_OEM_6, VK_OEM_7, VK_OEM_COMMA, VK_OEM_MINUS, VK_OEM_PERIOD,
+    VK_OEM_PLUS, VK_PAUSE, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SHIFT, VK_SNAPSHOT, VK_SPACE, VK_TAB, VK_UP, VK_VOLUME_DOWN,
+    VK_VOLUME_MUTE, VK_VOLUME_UP,
+};
+use windows::Win32::UI::WindowsAndMessaging::WHEEL_DELTA;
+
+/// Change the master volume by `delta` percent and return the new level
+pub(crate) fn change_volume(capture: bool, delta: i64) -> Result<u32, ActionError> {
+    let endpoint = endpoint_volume(capture)?;
+    unsafe {
+        let level = endpoint.GetMasterVolumeLevelScalar()?;
+        let level = (level + delta as f32 / 100.0).clamp(0.0, 1.0);
+        endpoint.SetMasterVolumeLevelScalar(level, std::ptr::null())?;
+        Ok((level * 100.0).round() as u32)
+    }
+}
+
+/// Current master volume in percent
+pub(crate) fn volume(capture: bool) -> Result<u32, ActionError> {
+    let endpoint = endpoint_volume(capture)?;
+    let level = unsafe { endpoint.GetMasterVolumeLevelScalar()? };
+    Ok((level * 100.0).round() as u32)
+}
+
+/// Whether the default output (or input) is muted
+pub(crate) fn muted(capture: bool) -> Result<bool, ActionError> {
+    let endpoint = endpoint_volume(capture)?;
+    Ok(unsafe { endpoint.GetMute()? }.as_bool())
+}
+
+/// Toggle mute on the default output (or input) and return whether it is
+/// now muted
+pub(crate) fn toggle_mute(capture: bool) -> Result<bool, ActionError> {
+    let endpoint = endpoint_volume(capture)?;
+    unsafe {
+        let muted = !endpoint.GetMute()?.as_bool();
+        endpoint.SetMute(muted, std::ptr::null())?;
+        Ok(muted)
+    }
+}
+
+/// Media keys Media Track sends
+#[derive(Clone, Copy)]
+pub(crate) enum MediaKey {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+/// Press and release a media key
+pub(crate) fn media_key(media: MediaKey) -> Result<(), ActionError> {
+    let vk = match media {
+        MediaKey::PlayPause => VK_MEDIA_PLAY_PAUSE,
+        MediaKey::Next => VK_MEDIA_NEXT_TRACK,
+        MediaKey::Previous => VK_MEDIA_PREV_TRACK,
+    };
+    send(&[key(vk, false), key(vk, true)])
+}
+
+/// Scroll the wheel on `axis` by `clicks`; positive scrolls up or right
+pub(crate) fn scroll(axis: Axis, clicks: i32) -> Result<(), ActionError> {
+    let mut input = wheel(clicks);
+    if axis == Axis::Horizontal {
+        input.Anonymous.mi.dwFlags = MOUSEEVENTF_HWHEEL;
+    }
+    send(&[input])
+}
+
+/// Move the pointer by `dx`, `dy` mickeys; with pointer acceleration off
+/// these are pixels
+pub(crate) fn mouse_move(dx: i32, dy: i32) -> Result<(), ActionError> {
+    send(&[INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx,
+                dy,
+                dwFlags: MOUSEEVENTF_MOVE,
+                ..Default::default()
+            },
+        },
+    }])
+}
+
+/// Ctrl + wheel by `clicks`; positive zooms in
+pub(crate) fn zoom(clicks: i32) -> Result<(), ActionError> {
+    send(&[key(VK_CONTROL, false), wheel(clicks), key(VK_CONTROL, true)])
+}
+
+/// Press Ctrl+`key` `repeat` times; the =/+ key zooms in
+pub(crate) fn ctrl_key(zoom: ZoomKey, repeat: u32) -> Result<(), ActionError> {
+    let vk = match zoom {
+        ZoomKey::In => VK_OEM_PLUS,
+        ZoomKey::Out => VK_OEM_MINUS,
+        ZoomKey::Reset => VK_0,
+    };
+    for _ in 0..repeat {
+        send(&[key(VK_CONTROL, false), key(vk, false), key(vk, true), key(VK_CONTROL, true)])?;
+    }
+    Ok(())
+}
+
+/// Press `chords`, given as Linux input codes, in order `repeat` times
+pub(crate) fn chords(chords: &[&[u16]], repeat: u32) -> Result<(), ActionError> {
+    let mut inputs = Vec::new();
+    for codes in chords {
+        let vks = codes.iter().map(|code| virtual_key(*code)).collect::<Option<Vec<_>>>().ok_or_else(|| {
+            ActionError::Unavailable(format!("no Windows key for input codes {:?}", codes))
+        })?;
+        inputs.extend(vks.iter().map(|vk| key(*vk, false)));
+        inputs.extend(vks.iter().rev().map(|vk| key(*vk, true)));
+    }
+    for _ in 0..repeat {
+        send(&inputs)?;
+    }
+    Ok(())
+}
+
+/// The virtual key for a Linux input event code from a chord or key spec
+fn virtual_key(code: u16) -> Option<VIRTUAL_KEY> {
+    Some(match code {
+        1 => VK_ESCAPE,
+        12 => VK_OEM_MINUS,
+        13 => VK_OEM_PLUS,
+        14 => VK_BACK,
+        15 => VK_TAB,
+        26 => VK_OEM_4,
+        27 => VK_OEM_6,
+        28 => VK_RETURN,
+        29 => VK_CONTROL,
+        39 => VK_OEM_1,
+        40 => VK_OEM_7,
+        41 => VK_OEM_3,
+        42 => VK_SHIFT,
+        43 => VK_OEM_5,
+        51 => VK_OEM_COMMA,
+        52 => VK_OEM_PERIOD,
+        53 => VK_OEM_2,
+        56 => VK_MENU,
+        57 => VK_SPACE,
+        58 => VK_CAPITAL,
+        59..=68 => VIRTUAL_KEY(VK_F1.0 + (code - 59)),
+        87 => VK_F11,
+        88 => VK_F12,
+        99 => VK_SNAPSHOT,
+        102 => VK_HOME,
+        103 => VK_UP,
+        104 => VK_PRIOR,
+        105 => VK_LEFT,
+        106 => VK_RIGHT,
+        107 => VK_END,
+        108 => VK_DOWN,
+        109 => VK_NEXT,
+        110 => VK_INSERT,
+        111 => VK_DELETE,
+        113 => VK_VOLUME_MUTE,
+        114 => VK_VOLUME_DOWN,
+        115 => VK_VOLUME_UP,
+        119 => VK_PAUSE,
+        125 => VK_LWIN,
+        127 => VK_APPS,
+        163 => VK_MEDIA_NEXT_TRACK,
+        164 => VK_MEDIA_PLAY_PAUSE,
+        165 => VK_MEDIA_PREV_TRACK,
+        166 => VK_MEDIA_STOP,
+        183..=194 => VIRTUAL_KEY(VK_F13.0 + (code - 183)),
+        // Letters and digits share their virtual key with the upper case character
+        _ => {
+            let (name, _) = keyspec::KEYS.iter().find(|(name, key)| *key == code && name.len() == 1)?;
+            VIRTUAL_KEY(u16::from(name.as_bytes()[0].to_ascii_uppercase()))
+        }
+    })
+}
+
+/// Volume control of the default playback or recording device. COM is
+/// initialised on the calling thread first; a repeat initialisation is
+/// harmless.
+fn endpoint_volume(capture: bool) -> windows::core::Result<IAudioEndpointVolume> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let device = enumerator.GetDefaultAudioEndpoint(if capture { eCapture } else { eRender }, eConsole)?;
+        device.Activate(CLSCTX_ALL, None)
+    }
+}
+
+fn wheel(clicks: i32) -> INPUT {
+    INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                mouseData: clicks.saturating_mul(WHEEL_DELTA as i32) as _,
+                dwFlags: MOUSEEVENTF_WHEEL,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+fn key(key: VIRTUAL_KEY, up: bool) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: key,
+                dwFlags: if up { KEYEVENTF_KEYUP } else { Default::default() },
+                ..Default::default()
+            },
+        },
+    }
+}
+
+fn send(inputs: &[INPUT]) -> Result<(), ActionError> {
+    let sent = unsafe { SendInput(inputs, size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err(windows::core::Error::from_thread().into());
+    }
+    Ok(())
+}